@@ -0,0 +1,79 @@
+use color_eyre::eyre::{eyre, Result};
+use std::path::PathBuf;
+
+use crate::{export::write_shader, graph::ShaderGraph};
+
+const USAGE: &str = "usage: bevy-shader-nodes compile <graph.ron> [-o <out.wgsl>] [--header]";
+
+struct CompileArgs {
+    graph: PathBuf,
+    output: Option<PathBuf>,
+    /// Start the output with the timestamped header, which is off so builds are reproducible.
+    header: bool,
+}
+
+impl CompileArgs {
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut graph = None;
+        let mut output = None;
+        let mut header = false;
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-o" | "--output" => {
+                    let path = args.next().ok_or_else(|| eyre!("{} expects a path", arg))?;
+
+                    output = Some(PathBuf::from(path));
+                }
+                "--header" => header = true,
+                _ if graph.is_none() && !arg.starts_with('-') => graph = Some(PathBuf::from(arg)),
+                _ => return Err(eyre!("unexpected argument '{}'", arg)),
+            }
+        }
+
+        Ok(Self {
+            graph: graph.ok_or_else(|| eyre!("missing graph path"))?,
+            output,
+            header,
+        })
+    }
+}
+
+/// Compiles a saved graph to WGSL, writing to stdout when no output path is given.
+///
+/// Returns the process exit code.
+pub fn compile(args: &[String]) -> i32 {
+    let args = match CompileArgs::parse(args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n{}", e, USAGE);
+            return 2;
+        }
+    };
+
+    match run(&args) {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            1
+        }
+    }
+}
+
+fn run(args: &CompileArgs) -> Result<()> {
+    let graph = ShaderGraph::load(&args.graph)
+        .map_err(|e| eyre!("failed to load {}: {}", args.graph.display(), e))?;
+    let builder = graph.resolve()?;
+    let shader = builder.build()?;
+
+    builder.validate(&shader)?;
+
+    match &args.output {
+        Some(path) => Ok(write_shader(path, &shader, args.header, Some(&args.graph))?),
+        None => {
+            print!("{}", shader);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use bevy::prelude::*;
+use nfd::Response;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    graph::{GraphFile, ShaderResolved},
+    shader::{ShaderBuilder, ShaderError},
+};
+
+#[derive(Default)]
+pub struct ExportPlugin;
+
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ExportAction>()
+            .init_resource::<ExportSettings>()
+            .init_resource::<LastResolved>()
+            .add_system(export_shortcuts)
+            .add_system(export_shader);
+    }
+}
+
+/// Something to do with the exported shader, sent by a shortcut or the toolbar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportAction {
+    /// Writes the shader to the export path, picking one if there is none yet.
+    Export,
+    /// Picks a new export path and writes the shader to it.
+    ExportAs,
+    ToggleLive,
+    /// Turns the header naming the source graph and generation time on or off.
+    ToggleHeader,
+}
+
+#[derive(Resource)]
+pub struct ExportSettings {
+    pub path: Option<PathBuf>,
+    /// Write the shader again every time the graph is resolved.
+    pub live: bool,
+    /// Start the exported file with comments naming the source graph and generation time.
+    pub header: bool,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            live: false,
+            header: true,
+        }
+    }
+}
+
+/// The shader of the last resolved material output.
+#[derive(Default, Resource)]
+struct LastResolved(Option<ShaderBuilder>);
+
+/// Writes `shader` to `path`, optionally prefixed with a header recording where it came from.
+pub fn write_shader(
+    path: impl AsRef<Path>,
+    shader: &str,
+    header: bool,
+    graph: Option<&Path>,
+) -> Result<(), ShaderError> {
+    let mut contents = String::new();
+
+    if header {
+        let generated = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+
+        contents.push_str("// Generated by bevy-shader-nodes, do not edit.\n");
+
+        if let Some(graph) = graph {
+            contents.push_str(&format!("// Source graph: {}\n", graph.display()));
+        }
+
+        contents.push_str(&format!(
+            "// Generated at: {} (seconds since the Unix epoch)\n",
+            generated
+        ));
+    }
+
+    contents.push_str(shader);
+    fs::write(path, contents)?;
+
+    Ok(())
+}
+
+fn pick_export_path() -> Option<PathBuf> {
+    match nfd::open_save_dialog(Some("wgsl"), None) {
+        Ok(Response::Okay(path)) => Some(PathBuf::from(path)),
+        Ok(_) => None,
+        Err(e) => {
+            error!("Failed to open file dialog: {}", e);
+            None
+        }
+    }
+}
+
+/// Ctrl+E exports the shader, Ctrl+Shift+E picks a new path first, Ctrl+L toggles live export
+/// and Ctrl+H toggles the header.
+fn export_shortcuts(keys: Res<Input<KeyCode>>, mut ev_action: EventWriter<ExportAction>) {
+    if !keys.any_pressed([KeyCode::LControl, KeyCode::RControl]) {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::L) {
+        ev_action.send(ExportAction::ToggleLive);
+    } else if keys.just_pressed(KeyCode::H) {
+        ev_action.send(ExportAction::ToggleHeader);
+    } else if keys.just_pressed(KeyCode::E) {
+        ev_action.send(if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+            ExportAction::ExportAs
+        } else {
+            ExportAction::Export
+        });
+    }
+}
+
+/// Writes the shader of the material output when asked to, or whenever it is resolved while live
+/// export is on.
+fn export_shader(
+    graph: Res<GraphFile>,
+    mut settings: ResMut<ExportSettings>,
+    mut last: ResMut<LastResolved>,
+    mut ev_action: EventReader<ExportAction>,
+    mut ev_resolved: EventReader<ShaderResolved>,
+) {
+    let mut export = false;
+
+    for event in ev_resolved.iter().filter(|event| event.output) {
+        last.0 = Some(event.builder.clone());
+        export |= settings.live;
+    }
+
+    for action in ev_action.iter() {
+        match action {
+            ExportAction::ToggleLive => {
+                settings.live = !settings.live;
+                info!("Live export {}", if settings.live { "on" } else { "off" });
+            }
+            ExportAction::ToggleHeader => {
+                settings.header = !settings.header;
+                info!(
+                    "Export header {}",
+                    if settings.header { "on" } else { "off" }
+                );
+            }
+            ExportAction::Export | ExportAction::ExportAs => {
+                if *action == ExportAction::ExportAs || settings.path.is_none() {
+                    // A cancelled dialog cancels the export.
+                    let Some(path) = pick_export_path() else {
+                        continue;
+                    };
+
+                    settings.path = Some(path);
+                }
+
+                export = true;
+            }
+        }
+    }
+
+    if !export {
+        return;
+    }
+
+    let (Some(path), Some(builder)) = (&settings.path, &last.0) else {
+        return;
+    };
+
+    // An invalid shader never replaces the file, which may still hold the last good one.
+    let result = builder.build().and_then(|shader| {
+        builder.validate(&shader)?;
+        write_shader(path, &shader, settings.header, graph.path.as_deref())
+    });
+
+    if let Err(e) = result {
+        error!("Failed to export {}: {}", path.display(), e);
+    }
+}
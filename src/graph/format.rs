@@ -0,0 +1,356 @@
+use bevy::prelude::Entity;
+use color_eyre::eyre::{eyre, Result};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::{fs, iter::Peekable, path::Path, str::Chars};
+
+use crate::shader::ShaderNodes;
+
+/// Version written to new graph files.
+///
+/// Bump this whenever a change to [`ShaderNodes`] cannot be read from older files and add the
+/// matching step to [`ShaderGraph::migrate`].
+pub const FORMAT_VERSION: u32 = 4;
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ShaderGraph {
+    pub version: u32,
+    pub nodes: Vec<GraphNode>,
+    pub connections: Vec<GraphConnection>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct GraphNode {
+    pub id: usize,
+    pub node: ShaderNodes,
+    pub position: [f32; 2],
+    /// The editor entity the node was captured from, unset in graphs read from a file.
+    #[serde(skip)]
+    pub entity: Option<Entity>,
+}
+
+impl GraphNode {
+    /// The entity the node's variables are named after, made up from its ID outside the editor.
+    pub fn entity(&self) -> Entity {
+        self.entity
+            .unwrap_or_else(|| Entity::from_raw(self.id as u32))
+    }
+}
+
+/// A link from the output `output` of node `from` to the input `input` of node `to`.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct GraphConnection {
+    pub from: usize,
+    pub output: String,
+    pub to: usize,
+    pub input: String,
+}
+
+/// Only the version of a graph file, read before the rest so older files can be migrated.
+#[derive(Deserialize)]
+#[serde(rename = "ShaderGraph")]
+struct GraphHeader {
+    version: u32,
+}
+
+impl Default for ShaderGraph {
+    fn default() -> Self {
+        Self {
+            version: FORMAT_VERSION,
+            nodes: vec![],
+            connections: vec![],
+        }
+    }
+}
+
+impl ShaderGraph {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_ron(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, self.to_ron()?)?;
+
+        Ok(())
+    }
+
+    pub fn from_ron(source: &str) -> Result<Self> {
+        let header: GraphHeader = ron::from_str(source)?;
+
+        if header.version > FORMAT_VERSION {
+            return Err(eyre!(
+                "graph format version {} is newer than the supported version {}",
+                header.version,
+                FORMAT_VERSION
+            ));
+        }
+
+        Self::migrate(header.version, source)
+    }
+
+    pub fn to_ron(&self) -> Result<String> {
+        Ok(ron::ser::to_string_pretty(self, PrettyConfig::default())?)
+    }
+
+    /// Reads a graph written with format `version` and brings it up to [`FORMAT_VERSION`].
+    ///
+    /// Each step rewrites the source of the version before it, so older files go through every
+    /// step after their own.
+    fn migrate(version: u32, source: &str) -> Result<Self> {
+        if version == 0 {
+            return Err(eyre!("unsupported graph format version {}", version));
+        }
+
+        let mut source = source.to_string();
+
+        // The preview node gained output settings.
+        if version < 2 {
+            source = rewrite_identifiers(&source, |identifier, _| {
+                (identifier == "MaterialPreview").then(|| "(())".to_string())
+            });
+        }
+
+        // Arithmetic nodes gained a default for each input instead of one shared by both.
+        if version < 3 {
+            source = rewrite_identifiers(&source, |identifier, chars| {
+                let arithmetic = ["Add", "Divide", "Modulo", "Multiply", "Power", "Subtract"];
+
+                if !arithmetic.contains(&identifier) || chars.next_if_eq(&'(').is_none() {
+                    return None;
+                }
+
+                let value = chars.by_ref().take_while(|c| *c != ')').collect::<String>();
+                let value = value.trim();
+
+                Some(format!("(({}, {}))", value, value))
+            });
+        }
+
+        // Noise nodes gained a default for their seed, which used to be zero.
+        if version < 4 {
+            source = rewrite_identifiers(&source, |identifier, chars| {
+                let noise = ["PerlinNoise", "SimplexNoise", "ValueNoise", "Voronoi"];
+
+                if !noise.contains(&identifier) || chars.next_if_eq(&'(').is_none() {
+                    return None;
+                }
+
+                let scale = chars.by_ref().take_while(|c| *c != ')').collect::<String>();
+
+                Some(format!("(({}, 0.0))", scale.trim()))
+            });
+        }
+
+        let mut graph: Self = ron::from_str(&source)?;
+
+        // The preview node's input was renamed to `color`.
+        if version < 2 {
+            let previews = graph
+                .nodes
+                .iter()
+                .filter(|node| matches!(node.node, ShaderNodes::MaterialPreview(_)))
+                .map(|node| node.id)
+                .collect::<Vec<_>>();
+
+            for connection in &mut graph.connections {
+                if previews.contains(&connection.to) && connection.input == "input" {
+                    connection.input = "color".to_string();
+                }
+            }
+        }
+
+        graph.version = FORMAT_VERSION;
+        Ok(graph)
+    }
+}
+
+/// Copies `source`, inserting whatever `rewrite` returns after each identifier.
+///
+/// `rewrite` may consume the characters following the identifier to replace them. Only
+/// identifiers are rewritten, so strings such as texture paths are copied as they are.
+fn rewrite_identifiers(
+    source: &str,
+    mut rewrite: impl FnMut(&str, &mut Peekable<Chars>) -> Option<String>,
+) -> String {
+    let mut migrated = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '"' {
+            migrated.push(c);
+
+            while let Some(c) = chars.next() {
+                migrated.push(c);
+
+                match c {
+                    '\\' => migrated.extend(chars.next()),
+                    '"' => break,
+                    _ => {}
+                }
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let mut identifier = String::from(c);
+
+            while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                identifier.push(c);
+            }
+
+            migrated.push_str(&identifier);
+            migrated.extend(rewrite(&identifier, &mut chars));
+        } else {
+            migrated.push(c);
+        }
+    }
+
+    migrated
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::default;
+    use bevy_flow_node::widgets::NumberInput;
+
+    use super::*;
+    use crate::shader::{BlendMode, Lighting, OutputSettings, TextureSettings};
+
+    fn node(id: usize, node: ShaderNodes) -> GraphNode {
+        GraphNode {
+            id,
+            node,
+            position: [id as f32 * 100.0, -50.0],
+            entity: None,
+        }
+    }
+
+    fn connection(from: usize, output: &str, to: usize, input: &str) -> GraphConnection {
+        GraphConnection {
+            from,
+            output: output.to_string(),
+            to,
+            input: input.to_string(),
+        }
+    }
+
+    #[test]
+    fn graphs_read_back_as_written() {
+        let graph = ShaderGraph {
+            nodes: vec![
+                node(0, ShaderNodes::Float(NumberInput { value: 0.25 })),
+                node(
+                    1,
+                    ShaderNodes::Texture(TextureSettings {
+                        path: Some("textures/\"quoted\".png".to_string()),
+                        ..default()
+                    }),
+                ),
+                node(
+                    2,
+                    ShaderNodes::MaterialPreview(OutputSettings {
+                        blend_mode: BlendMode::Mask(0.3),
+                        lighting: Lighting::Pbr,
+                    }),
+                ),
+            ],
+            connections: vec![
+                connection(1, "color", 2, "base_color"),
+                connection(0, "value", 2, "alpha"),
+            ],
+            ..default()
+        };
+        let source = graph.to_ron().unwrap();
+        let read = ShaderGraph::from_ron(&source).unwrap();
+
+        assert_eq!(read.version, FORMAT_VERSION);
+        assert_eq!(read.to_ron().unwrap(), source);
+    }
+
+    #[test]
+    fn version_1_graphs_are_migrated() {
+        let source = r#"(
+    version: 1,
+    nodes: [
+        (id: 0, node: Texture((path: Some("MaterialPreview.png"))), position: (0.0, 0.0)),
+        (id: 1, node: MaterialPreview, position: (300.0, 0.0)),
+    ],
+    connections: [
+        (from: 0, output: "color", to: 1, input: "input"),
+    ],
+)"#;
+        let graph = ShaderGraph::from_ron(source).unwrap();
+
+        assert_eq!(graph.version, FORMAT_VERSION);
+        assert!(matches!(
+            &graph.nodes[0].node,
+            ShaderNodes::Texture(settings)
+                if settings.path.as_deref() == Some("MaterialPreview.png")
+        ));
+        assert!(matches!(
+            &graph.nodes[1].node,
+            ShaderNodes::MaterialPreview(settings) if *settings == OutputSettings::default()
+        ));
+        assert_eq!(graph.connections, vec![connection(0, "color", 1, "color")]);
+    }
+
+    #[test]
+    fn version_2_arithmetic_defaults_are_split() {
+        let source = r#"(
+    version: 2,
+    nodes: [
+        (id: 0, node: Multiply( 0.5 ), position: (0.0, 0.0)),
+        (id: 1, node: Subtract(-2.0), position: (100.0, 0.0)),
+        (id: 2, node: Float(0.25), position: (200.0, 0.0)),
+    ],
+    connections: [
+        (from: 2, output: "value", to: 0, input: "a"),
+    ],
+)"#;
+        let graph = ShaderGraph::from_ron(source).unwrap();
+
+        assert_eq!(graph.version, FORMAT_VERSION);
+        assert!(matches!(
+            graph.nodes[0].node,
+            ShaderNodes::Multiply([0.5, 0.5])
+        ));
+        assert!(matches!(
+            graph.nodes[1].node,
+            ShaderNodes::Subtract([-2.0, -2.0])
+        ));
+        assert!(matches!(
+            &graph.nodes[2].node,
+            ShaderNodes::Float(input) if input.value == 0.25
+        ));
+    }
+
+    #[test]
+    fn version_3_noise_nodes_get_a_seed() {
+        let source = r#"(
+    version: 3,
+    nodes: [
+        (id: 0, node: Voronoi(4.0), position: (0.0, 0.0)),
+        (id: 1, node: PerlinNoise(8.0), position: (100.0, 0.0)),
+    ],
+    connections: [],
+)"#;
+        let graph = ShaderGraph::from_ron(source).unwrap();
+
+        assert_eq!(graph.version, FORMAT_VERSION);
+        assert!(matches!(
+            graph.nodes[0].node,
+            ShaderNodes::Voronoi([4.0, 0.0])
+        ));
+        assert!(matches!(
+            graph.nodes[1].node,
+            ShaderNodes::PerlinNoise([8.0, 0.0])
+        ));
+    }
+
+    #[test]
+    fn newer_graphs_are_rejected() {
+        let source = format!(
+            "(version: {}, nodes: [], connections: [])",
+            FORMAT_VERSION + 1
+        );
+
+        assert!(ShaderGraph::from_ron(&source).is_err());
+    }
+}
@@ -0,0 +1,10 @@
+mod format;
+mod plugin;
+mod resolve;
+mod resolved;
+mod sockets;
+
+pub use format::{GraphConnection, GraphNode, ShaderGraph, FORMAT_VERSION};
+pub use plugin::{FileAction, GraphFile, GraphFilePlugin};
+pub use resolved::{ResolvePlugin, ShaderResolved};
+pub use sockets::SocketPlugin;
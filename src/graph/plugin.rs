@@ -0,0 +1,367 @@
+use bevy::{prelude::*, utils::HashMap};
+use bevy_flow_node::{FlowNode, FlowNodeConnection, FlowNodeInput, FlowNodeOutput, FlowNodeSet};
+use nfd::Response;
+use std::path::PathBuf;
+
+use crate::{
+    graph::{GraphConnection, GraphNode, ShaderGraph, FORMAT_VERSION},
+    shader::ShaderNodes,
+};
+
+#[derive(Default)]
+pub struct GraphFilePlugin;
+
+impl Plugin for GraphFilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<FileAction>()
+            .init_resource::<GraphFile>()
+            .init_resource::<PendingConnections>()
+            .add_system(file_shortcuts)
+            .add_system(open_graph)
+            .add_system(save_graph)
+            .add_system(rebuild_changed_nodes)
+            .add_system(connect_loaded_nodes);
+    }
+}
+
+/// Something to do with the graph file, sent by a shortcut or the toolbar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileAction {
+    Open,
+    /// Saves to the file the graph came from, picking one if it has none yet.
+    Save,
+    /// Picks a new file to save to.
+    SaveAs,
+}
+
+/// The file the current graph was last saved to or opened from.
+#[derive(Default, Resource)]
+pub struct GraphFile {
+    pub path: Option<PathBuf>,
+}
+
+/// A connection from the output `output` of node `from` to the input `input` of node `to`,
+/// waiting for the sockets of both nodes to be built.
+struct PendingConnection {
+    from: Entity,
+    output: String,
+    to: Entity,
+    input: String,
+}
+
+/// Connections of opened or rebuilt nodes.
+#[derive(Default, Resource)]
+struct PendingConnections {
+    connections: Vec<PendingConnection>,
+}
+
+fn ctrl_pressed(keys: &Input<KeyCode>) -> bool {
+    keys.any_pressed([KeyCode::LControl, KeyCode::RControl])
+}
+
+fn shift_pressed(keys: &Input<KeyCode>) -> bool {
+    keys.any_pressed([KeyCode::LShift, KeyCode::RShift])
+}
+
+fn pick_file(save: bool) -> Option<PathBuf> {
+    let result = if save {
+        nfd::open_save_dialog(Some("ron"), None)
+    } else {
+        nfd::open_file_dialog(Some("ron"), None)
+    };
+
+    match result {
+        Ok(Response::Okay(path)) => Some(PathBuf::from(path)),
+        Ok(_) => None,
+        Err(e) => {
+            error!("Failed to open file dialog: {}", e);
+            None
+        }
+    }
+}
+
+/// Ctrl+O opens a graph, Ctrl+S saves it and Ctrl+Shift+S saves it to a new file.
+fn file_shortcuts(keys: Res<Input<KeyCode>>, mut ev_action: EventWriter<FileAction>) {
+    if !ctrl_pressed(&keys) {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::O) {
+        ev_action.send(FileAction::Open);
+    } else if keys.just_pressed(KeyCode::S) {
+        ev_action.send(if shift_pressed(&keys) {
+            FileAction::SaveAs
+        } else {
+            FileAction::Save
+        });
+    }
+}
+
+fn open_graph(
+    mut commands: Commands,
+    mut ev_action: EventReader<FileAction>,
+    mut file: ResMut<GraphFile>,
+    mut pending: ResMut<PendingConnections>,
+    nodes: Query<Entity, With<FlowNode<ShaderNodes>>>,
+    connections: Query<Entity, With<FlowNodeConnection>>,
+) {
+    if !ev_action.iter().any(|action| *action == FileAction::Open) {
+        return;
+    }
+
+    let Some(path) = pick_file(false) else {
+        return;
+    };
+    let graph = match ShaderGraph::load(&path) {
+        Ok(graph) => graph,
+        Err(e) => {
+            error!("Failed to open {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    for entity in connections.iter().chain(nodes.iter()) {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let mut entities = HashMap::new();
+
+    for node in graph.nodes {
+        let mut template = node.node.template();
+
+        template.position = Vec2::from(node.position);
+        entities.insert(node.id, commands.spawn(template).id());
+    }
+
+    pending.connections = graph
+        .connections
+        .into_iter()
+        .filter_map(|connection| {
+            let (Some(from), Some(to)) =
+                (entities.get(&connection.from), entities.get(&connection.to))
+            else {
+                error!(
+                    "Dropped the connection from node {} to node {}: no such node in {}",
+                    connection.from,
+                    connection.to,
+                    path.display()
+                );
+                return None;
+            };
+
+            Some(PendingConnection {
+                from: *from,
+                output: connection.output,
+                to: *to,
+                input: connection.input,
+            })
+        })
+        .collect();
+    file.path = Some(path);
+}
+
+fn save_graph(
+    mut ev_action: EventReader<FileAction>,
+    mut file: ResMut<GraphFile>,
+    nodes: Query<(Entity, &FlowNode<ShaderNodes>, &Transform)>,
+    inputs: Query<(&FlowNodeInput, &Parent)>,
+    outputs: Query<(&FlowNodeOutput, &Parent)>,
+    connections: Query<&FlowNodeConnection>,
+) {
+    let Some(action) = ev_action
+        .iter()
+        .filter(|action| **action != FileAction::Open)
+        .last()
+    else {
+        return;
+    };
+    let path = match &file.path {
+        Some(path) if *action == FileAction::Save => path.clone(),
+        _ => match pick_file(true) {
+            Some(path) => path,
+            None => return,
+        },
+    };
+    let (graph, _) = capture_graph(&nodes, &inputs, &outputs, &connections);
+
+    match graph.save(&path) {
+        Ok(()) => file.path = Some(path),
+        Err(e) => error!("Failed to save {}: {}", path.display(), e),
+    }
+}
+
+/// The graph currently in the editor, along with the ID given to each node entity.
+pub(super) fn capture_graph(
+    nodes: &Query<(Entity, &FlowNode<ShaderNodes>, &Transform)>,
+    inputs: &Query<(&FlowNodeInput, &Parent)>,
+    outputs: &Query<(&FlowNodeOutput, &Parent)>,
+    connections: &Query<&FlowNodeConnection>,
+) -> (ShaderGraph, HashMap<Entity, usize>) {
+    let ids = nodes
+        .iter()
+        .enumerate()
+        .map(|(id, (entity, _, _))| (entity, id))
+        .collect::<HashMap<_, _>>();
+    let graph = ShaderGraph {
+        version: FORMAT_VERSION,
+        nodes: nodes
+            .iter()
+            .map(|(entity, node, transform)| GraphNode {
+                id: ids[&entity],
+                node: node.node.clone(),
+                position: transform.translation.truncate().into(),
+                entity: Some(entity),
+            })
+            .collect(),
+        connections: connections
+            .iter()
+            .filter_map(|connection| {
+                let (output, from) = outputs.get(connection.output).ok()?;
+                let (input, to) = inputs.get(connection.input).ok()?;
+
+                Some(GraphConnection {
+                    from: *ids.get(&from.get())?,
+                    output: output.label.clone(),
+                    to: *ids.get(&to.get())?,
+                    input: input.label.clone(),
+                })
+            })
+            .collect(),
+    };
+
+    (graph, ids)
+}
+
+/// Rebuilds nodes whose settings changed their inputs, such as a preview switched to PBR
+/// lighting, as sockets are only made when a node is spawned.
+///
+/// Connections are kept on the inputs the rebuilt node still has, or the ones replacing them.
+fn rebuild_changed_nodes(
+    mut commands: Commands,
+    mut pending: ResMut<PendingConnections>,
+    nodes: Query<
+        (Entity, &FlowNode<ShaderNodes>, &Transform, &Children),
+        Changed<FlowNode<ShaderNodes>>,
+    >,
+    inputs: Query<(&FlowNodeInput, &Parent)>,
+    outputs: Query<(&FlowNodeOutput, &Parent)>,
+    connections: Query<(Entity, &FlowNodeConnection)>,
+) {
+    for (entity, node, transform, children) in nodes.iter() {
+        let mut labels = children
+            .iter()
+            .filter_map(|child| inputs.get(*child).ok())
+            .map(|(input, _)| input.label.as_str())
+            .collect::<Vec<_>>();
+        let mut template = node.node.clone().template();
+        let mut template_labels = template
+            .inputs
+            .iter()
+            .flatten()
+            .map(|input| input.label.as_str())
+            .collect::<Vec<_>>();
+
+        labels.sort_unstable();
+        template_labels.sort_unstable();
+
+        // Nodes without sockets are still being built.
+        if labels.is_empty() || labels == template_labels {
+            continue;
+        }
+
+        template.position = transform.translation.truncate();
+
+        let rebuilt = commands.spawn(template).id();
+        let replace = |node: Entity| if node == entity { rebuilt } else { node };
+
+        for (connection_entity, connection) in connections.iter() {
+            let (Ok((output, from)), Ok((input, to))) =
+                (outputs.get(connection.output), inputs.get(connection.input))
+            else {
+                continue;
+            };
+
+            if from.get() == entity || to.get() == entity {
+                let input = if to.get() == entity {
+                    node.node.rebuilt_input(&input.label)
+                } else {
+                    &input.label
+                };
+
+                pending.connections.push(PendingConnection {
+                    from: replace(from.get()),
+                    output: output.label.clone(),
+                    to: replace(to.get()),
+                    input: input.to_string(),
+                });
+                commands.entity(connection_entity).despawn();
+            }
+        }
+
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Connects pending connections once the sockets of both their nodes are built.
+///
+/// Connections to a node that was removed or built without the named socket are dropped and
+/// reported rather than waited on.
+fn connect_loaded_nodes(
+    mut commands: Commands,
+    mut pending: ResMut<PendingConnections>,
+    entities: Query<()>,
+    inputs: Query<(Entity, &FlowNodeInput, &Parent)>,
+    outputs: Query<(Entity, &FlowNodeOutput, &Parent)>,
+) {
+    if pending.connections.is_empty() {
+        return;
+    }
+
+    let built = |node: Entity| {
+        inputs.iter().any(|(_, _, parent)| parent.get() == node)
+            || outputs.iter().any(|(_, _, parent)| parent.get() == node)
+    };
+
+    pending.connections.retain(|connection| {
+        if !entities.contains(connection.from) || !entities.contains(connection.to) {
+            error!(
+                "Dropped the connection from '{}' to '{}': its node was removed",
+                connection.output, connection.input
+            );
+            return false;
+        }
+
+        if !built(connection.from) || !built(connection.to) {
+            return true;
+        }
+
+        let output = outputs
+            .iter()
+            .find(|(_, output, parent)| {
+                parent.get() == connection.from && output.label == connection.output
+            })
+            .map(|(entity, _, _)| entity);
+        let input = inputs
+            .iter()
+            .find(|(_, input, parent)| {
+                parent.get() == connection.to && input.label == connection.input
+            })
+            .map(|(entity, _, _)| entity);
+
+        match (input, output) {
+            (Some(input), Some(output)) => {
+                commands.spawn(FlowNodeConnection { input, output });
+            }
+            (None, _) => error!(
+                "Dropped the connection from '{}' to '{}': the node has no such input",
+                connection.output, connection.input
+            ),
+            (_, None) => error!(
+                "Dropped the connection from '{}' to '{}': the node has no such output",
+                connection.output, connection.input
+            ),
+        }
+
+        false
+    });
+}
@@ -0,0 +1,90 @@
+use bevy::prelude::Entity;
+use bevy_flow_node::FlowNodeSet;
+use color_eyre::eyre::{eyre, Result};
+use std::collections::HashMap;
+
+use crate::{
+    graph::{GraphNode, ShaderGraph},
+    shader::{ShaderBuilder, ShaderIO, ShaderNodes},
+};
+
+impl ShaderGraph {
+    /// Resolves the graph's output node the same way the editor does, without rendering.
+    pub fn resolve(&self) -> Result<ShaderBuilder> {
+        let outputs = self
+            .nodes
+            .iter()
+            .filter(|node| !matches!(node.node, ShaderNodes::Print))
+            .filter(|node| node.node.clone().template().outputs.is_none())
+            .collect::<Vec<_>>();
+
+        match outputs[..] {
+            [output] => self.resolve_node(output, None, &mut vec![]),
+            [] => Err(eyre!("graph has no output node")),
+            _ => Err(eyre!(
+                "graph has {} output nodes, expected one",
+                outputs.len()
+            )),
+        }
+    }
+
+    /// Resolves the node captured from `entity`, such as an output or Print node.
+    pub fn resolve_entity(&self, entity: Entity) -> Result<ShaderBuilder> {
+        let node = self
+            .nodes
+            .iter()
+            .find(|node| node.entity == Some(entity))
+            .ok_or_else(|| eyre!("graph has no node for entity {:?}", entity))?;
+
+        self.resolve_node(node, None, &mut vec![])
+    }
+
+    /// The type `output` of node `id` resolves to with the graph's current connections.
+    pub fn output_io(&self, id: usize, output: &str) -> Result<ShaderIO> {
+        self.resolve_node(self.node(id)?, Some(output), &mut vec![])
+            .map(|builder| builder.output)
+    }
+
+    fn node(&self, id: usize) -> Result<&GraphNode> {
+        self.nodes
+            .iter()
+            .find(|node| node.id == id)
+            .ok_or_else(|| eyre!("connection refers to missing node {}", id))
+    }
+
+    fn resolve_node(
+        &self,
+        node: &GraphNode,
+        output: Option<&str>,
+        visiting: &mut Vec<usize>,
+    ) -> Result<ShaderBuilder> {
+        if visiting.contains(&node.id) {
+            return Err(eyre!("graph contains a cycle through node {}", node.id));
+        }
+
+        visiting.push(node.id);
+
+        let mut inputs = HashMap::new();
+
+        for input in node.node.clone().template().inputs.unwrap_or_default() {
+            let connection = self
+                .connections
+                .iter()
+                .find(|connection| connection.to == node.id && connection.input == input.label);
+            let value = match connection {
+                Some(connection) => Some(self.resolve_node(
+                    self.node(connection.from)?,
+                    Some(&connection.output),
+                    visiting,
+                )?),
+                None => None,
+            };
+
+            inputs.insert(input.label, value);
+        }
+
+        visiting.pop();
+
+        Ok(node.node.resolve_node(node.entity(), inputs, output))
+    }
+}
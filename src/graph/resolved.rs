@@ -0,0 +1,98 @@
+use bevy::prelude::*;
+use bevy_flow_node::{FlowNode, FlowNodeConnection, FlowNodeEvent, FlowNodeInput, FlowNodeOutput};
+
+use crate::{
+    graph::plugin::capture_graph,
+    shader::{ShaderBuilder, ShaderNodes},
+};
+
+#[derive(Default)]
+pub struct ResolvePlugin;
+
+impl Plugin for ResolvePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ShaderResolved>()
+            .add_system(resolve_graph)
+            .add_system(print_shaders);
+    }
+}
+
+/// A node of the editor's graph resolved by [`ShaderGraph`](crate::graph::ShaderGraph), sent
+/// for every `FlowNodeEvent::Resolved`.
+///
+/// Unlike the library's own resolution, every node's variables are named after its entity.
+pub struct ShaderResolved {
+    pub node: Entity,
+    /// Whether the node is a material output, whose shader is the one previewed and exported.
+    pub output: bool,
+    pub builder: ShaderBuilder,
+}
+
+fn resolve_graph(
+    mut ev_node: EventReader<FlowNodeEvent<ShaderNodes>>,
+    mut ev_resolved: EventWriter<ShaderResolved>,
+    nodes: Query<(Entity, &FlowNode<ShaderNodes>, &Transform)>,
+    inputs: Query<(&FlowNodeInput, &Parent)>,
+    outputs: Query<(&FlowNodeOutput, &Parent)>,
+    connections: Query<&FlowNodeConnection>,
+) {
+    let mut resolved = Vec::new();
+
+    for event in ev_node.iter() {
+        if let FlowNodeEvent::Resolved((entity, _)) = event {
+            resolved.push(*entity);
+        }
+    }
+
+    if resolved.is_empty() {
+        return;
+    }
+
+    let (graph, _) = capture_graph(&nodes, &inputs, &outputs, &connections);
+
+    for node in resolved {
+        let output = matches!(
+            nodes.get(node),
+            Ok((
+                _,
+                FlowNode {
+                    node: ShaderNodes::MaterialPreview(_),
+                    ..
+                },
+                _
+            ))
+        );
+
+        match graph.resolve_entity(node) {
+            Ok(builder) => ev_resolved.send(ShaderResolved {
+                node,
+                output,
+                builder,
+            }),
+            Err(e) => error!("Failed to resolve the graph: {}", e),
+        }
+    }
+}
+
+/// Prints the shader reaching each Print node.
+fn print_shaders(
+    mut ev_resolved: EventReader<ShaderResolved>,
+    nodes: Query<&FlowNode<ShaderNodes>>,
+) {
+    for event in ev_resolved.iter() {
+        if !matches!(
+            nodes.get(event.node),
+            Ok(FlowNode {
+                node: ShaderNodes::Print,
+                ..
+            })
+        ) {
+            continue;
+        }
+
+        match event.builder.build() {
+            Ok(shader) => println!("{}", shader),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+}
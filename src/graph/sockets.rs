@@ -0,0 +1,132 @@
+use bevy::{prelude::*, sprite::Anchor};
+use bevy_flow_node::{
+    assets::DefaultAssets,
+    FlowNode,
+    FlowNodeConnection,
+    FlowNodeInput,
+    FlowNodeOutput,
+};
+
+use crate::{
+    graph::{plugin::capture_graph, ShaderResolved},
+    shader::{ShaderIO, ShaderNodes},
+};
+
+/// Color of sockets that take or give more than one type.
+const ANY_COLOR: Color = Color::rgb(0.8, 0.8, 0.8);
+const CONVERSION_COLOR: Color = Color::rgb(0.95, 0.6, 0.2);
+
+#[derive(Default)]
+pub struct SocketPlugin;
+
+impl Plugin for SocketPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(color_sockets).add_system(mark_conversions);
+    }
+}
+
+/// Labels an input whose connected value is truncated to fit it.
+#[derive(Component)]
+struct ConversionIndicator;
+
+fn io_color(types: &[ShaderIO]) -> Color {
+    match types {
+        [ShaderIO::F32] => Color::rgb(0.6, 0.6, 0.6),
+        [ShaderIO::Vec2] => Color::rgb(0.3, 0.8, 0.4),
+        [ShaderIO::Vec3] => Color::rgb(0.9, 0.8, 0.2),
+        [ShaderIO::Vec4] => Color::rgb(0.7, 0.4, 0.9),
+        _ => ANY_COLOR,
+    }
+}
+
+fn color_sockets(
+    nodes: Query<&FlowNode<ShaderNodes>>,
+    mut inputs: Query<(&FlowNodeInput, &Parent, &mut Sprite), Added<FlowNodeInput>>,
+    mut outputs: Query<(&FlowNodeOutput, &Parent, &mut Sprite), Added<FlowNodeOutput>>,
+) {
+    for (input, parent, mut sprite) in inputs.iter_mut() {
+        if let Ok(node) = nodes.get(parent.get()) {
+            sprite.color = io_color(node.node.input_types(&input.label));
+        }
+    }
+
+    for (output, parent, mut sprite) in outputs.iter_mut() {
+        if let Ok(node) = nodes.get(parent.get()) {
+            sprite.color = io_color(node.node.output_types(&output.label));
+        }
+    }
+}
+
+/// Resolves the type flowing through every connection whenever the links change or the graph is
+/// resolved, which catches nodes whose output type changed, and labels the inputs that truncate it.
+#[allow(clippy::too_many_arguments)]
+fn mark_conversions(
+    mut commands: Commands,
+    assets: Res<DefaultAssets>,
+    added: Query<(), Added<FlowNodeConnection>>,
+    mut removed: RemovedComponents<FlowNodeConnection>,
+    mut ev_resolved: EventReader<ShaderResolved>,
+    nodes: Query<(Entity, &FlowNode<ShaderNodes>, &Transform)>,
+    inputs: Query<(&FlowNodeInput, &Parent)>,
+    outputs: Query<(&FlowNodeOutput, &Parent)>,
+    connections: Query<&FlowNodeConnection>,
+    indicators: Query<Entity, With<ConversionIndicator>>,
+) {
+    let resolved = ev_resolved.iter().count() > 0;
+
+    if added.is_empty() && removed.iter().count() == 0 && !resolved {
+        return;
+    }
+
+    for entity in indicators.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let (graph, ids) = capture_graph(&nodes, &inputs, &outputs, &connections);
+
+    for connection in connections.iter() {
+        let Ok((output, from)) = outputs.get(connection.output) else {
+            continue;
+        };
+        let Ok((input, to)) = inputs.get(connection.input) else {
+            continue;
+        };
+        let Ok((_, node, _)) = nodes.get(to.get()) else {
+            continue;
+        };
+        let Some(from) = ids.get(&from.get()) else {
+            continue;
+        };
+        let Ok(io) = graph.output_io(*from, &output.label) else {
+            continue;
+        };
+        let Some(target) = node.node.input_types(&input.label).iter().max().copied() else {
+            continue;
+        };
+
+        if io <= target {
+            continue;
+        }
+
+        let indicator = commands
+            .spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        format!("{:?} -> {:?}", io, target),
+                        TextStyle {
+                            font: assets.font.clone(),
+                            font_size: 12.0,
+                            color: CONVERSION_COLOR,
+                        },
+                    ),
+                    text_anchor: Anchor::BottomLeft,
+                    transform: Transform::from_xyz(0.0, 8.0, 1.0),
+                    ..default()
+                },
+                ConversionIndicator,
+            ))
+            .id();
+
+        commands.entity(connection.input).add_child(indicator);
+    }
+}
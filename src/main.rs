@@ -1,13 +1,30 @@
 use bevy::prelude::*;
 use bevy_flow_node::{FlowNodeMenuPlugin, FlowNodePlugins, FlowNodeSet, PanCameraPlugin};
 
+mod cli;
+mod export;
+mod graph;
 mod menu;
 mod shader;
+mod toolbar;
 mod widgets;
 
-use crate::{menu::Menu, shader::ShaderNodes, widgets::WidgetPlugins};
+use crate::{
+    export::ExportPlugin,
+    graph::{GraphFilePlugin, ResolvePlugin, SocketPlugin},
+    menu::Menu,
+    shader::{OutputSettings, ShaderNodes},
+    toolbar::ToolbarPlugin,
+    widgets::WidgetPlugins,
+};
 
 fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    if args.first().map(String::as_str) == Some("compile") {
+        std::process::exit(cli::compile(&args[1..]));
+    }
+
     App::new()
         .insert_resource(ClearColor(Color::rgb(0.12, 0.12, 0.12)))
         .insert_resource(Msaa::Sample4)
@@ -15,11 +32,16 @@ fn main() {
         .add_plugins(FlowNodePlugins::<ShaderNodes>::default())
         .add_plugins(WidgetPlugins)
         .add_plugin(FlowNodeMenuPlugin::<Menu, ShaderNodes>::default())
+        .add_plugin(ExportPlugin)
+        .add_plugin(GraphFilePlugin)
+        .add_plugin(ResolvePlugin)
+        .add_plugin(SocketPlugin)
         .add_plugin(PanCameraPlugin)
+        .add_plugin(ToolbarPlugin)
         .add_startup_system(setup)
         .run();
 }
 
 fn setup(mut commands: Commands) {
-    commands.spawn(ShaderNodes::MaterialPreview.template());
+    commands.spawn(ShaderNodes::MaterialPreview(OutputSettings::default()).template());
 }
@@ -1,7 +1,16 @@
 use bevy::prelude::*;
 use bevy_flow_node::{widgets::NumberInput, FlowNodeMenu};
+use std::f32::consts::{E, PI, TAU};
 
-use crate::shader::ShaderNodes;
+use crate::shader::{
+    AddressMode,
+    FilterMode,
+    OutputSettings,
+    ShaderIO,
+    ShaderNodes,
+    ShaderParameter,
+    TextureSettings,
+};
 
 #[derive(Default, Resource)]
 pub struct Menu;
@@ -11,7 +20,59 @@ impl FlowNodeMenu<ShaderNodes> for Menu {
         vec![
             ("Normal".to_string(), ShaderNodes::Normal),
             ("UV".to_string(), ShaderNodes::UV),
-            ("Texture".to_string(), ShaderNodes::Texture),
+            ("Position".to_string(), ShaderNodes::Position),
+            ("View Direction".to_string(), ShaderNodes::ViewDirection),
+            ("Tangent".to_string(), ShaderNodes::Tangent),
+            ("Bitangent".to_string(), ShaderNodes::Bitangent),
+            ("Vertex Color".to_string(), ShaderNodes::VertexColor),
+            ("Time".to_string(), ShaderNodes::Time),
+            ("Varying".to_string(), ShaderNodes::Varying),
+            (
+                "Texture".to_string(),
+                ShaderNodes::Texture(TextureSettings::default()),
+            ),
+            (
+                "Texture (Tiled)".to_string(),
+                ShaderNodes::Texture(TextureSettings {
+                    address_mode: AddressMode::Repeat,
+                    ..default()
+                }),
+            ),
+            (
+                "Texture (Mirrored)".to_string(),
+                ShaderNodes::Texture(TextureSettings {
+                    address_mode: AddressMode::Mirror,
+                    ..default()
+                }),
+            ),
+            (
+                "Texture (Pixel Art)".to_string(),
+                ShaderNodes::Texture(TextureSettings {
+                    mag_filter: FilterMode::Nearest,
+                    min_filter: FilterMode::Nearest,
+                    ..default()
+                }),
+            ),
+            (
+                "Float".to_string(),
+                ShaderNodes::Float(NumberInput::default()),
+            ),
+            ("Vec2".to_string(), ShaderNodes::Vec2([0.0; 2])),
+            ("Vec3".to_string(), ShaderNodes::Vec3([0.0; 3])),
+            ("Vec4".to_string(), ShaderNodes::Vec4([0.0; 4])),
+            (
+                "Pi".to_string(),
+                ShaderNodes::Float(NumberInput { value: PI }),
+            ),
+            (
+                "Tau".to_string(),
+                ShaderNodes::Float(NumberInput { value: TAU }),
+            ),
+            (
+                "E".to_string(),
+                ShaderNodes::Float(NumberInput { value: E }),
+            ),
+            ("Color".to_string(), ShaderNodes::Color([1.0; 4])),
             (
                 "Extend".to_string(),
                 ShaderNodes::Extend(NumberInput::default()),
@@ -19,8 +80,63 @@ impl FlowNodeMenu<ShaderNodes> for Menu {
             ("Saturate".to_string(), ShaderNodes::Saturate),
             ("Component".to_string(), ShaderNodes::Component),
             ("Vector".to_string(), ShaderNodes::Vector),
+            ("Add".to_string(), ShaderNodes::Add([0.0; 2])),
+            ("Subtract".to_string(), ShaderNodes::Subtract([0.0; 2])),
+            ("Multiply".to_string(), ShaderNodes::Multiply([1.0; 2])),
+            ("Divide".to_string(), ShaderNodes::Divide([1.0; 2])),
+            ("Power".to_string(), ShaderNodes::Power([1.0; 2])),
+            ("Modulo".to_string(), ShaderNodes::Modulo([1.0; 2])),
+            ("Mix".to_string(), ShaderNodes::Mix),
+            ("Step".to_string(), ShaderNodes::Step),
+            ("Smoothstep".to_string(), ShaderNodes::Smoothstep),
+            ("Dot".to_string(), ShaderNodes::Dot),
+            ("Cross".to_string(), ShaderNodes::Cross),
+            ("Normalize".to_string(), ShaderNodes::Normalize),
+            ("Length".to_string(), ShaderNodes::Length),
+            ("Distance".to_string(), ShaderNodes::Distance),
+            ("Reflect".to_string(), ShaderNodes::Reflect),
+            (
+                "Fresnel".to_string(),
+                ShaderNodes::Fresnel(NumberInput { value: 5.0 }),
+            ),
+            (
+                "Value Noise".to_string(),
+                ShaderNodes::ValueNoise([8.0, 0.0]),
+            ),
+            (
+                "Perlin Noise".to_string(),
+                ShaderNodes::PerlinNoise([8.0, 0.0]),
+            ),
+            (
+                "Simplex Noise".to_string(),
+                ShaderNodes::SimplexNoise([8.0, 0.0]),
+            ),
+            ("Voronoi".to_string(), ShaderNodes::Voronoi([8.0, 0.0])),
+            (
+                "Float Parameter".to_string(),
+                ShaderNodes::Parameter(ShaderParameter::new("float", ShaderIO::F32, [0.0; 4])),
+            ),
+            (
+                "Vec2 Parameter".to_string(),
+                ShaderNodes::Parameter(ShaderParameter::new("offset", ShaderIO::Vec2, [0.0; 4])),
+            ),
+            (
+                "Vec3 Parameter".to_string(),
+                ShaderNodes::Parameter(ShaderParameter::new("direction", ShaderIO::Vec3, [0.0; 4])),
+            ),
+            (
+                "Vec4 Parameter".to_string(),
+                ShaderNodes::Parameter(ShaderParameter::new("vector", ShaderIO::Vec4, [0.0; 4])),
+            ),
+            (
+                "Color Parameter".to_string(),
+                ShaderNodes::Parameter(ShaderParameter::new("color", ShaderIO::Vec4, [1.0; 4])),
+            ),
             ("Print".to_string(), ShaderNodes::Print),
-            ("Preview".to_string(), ShaderNodes::MaterialPreview),
+            (
+                "Preview".to_string(),
+                ShaderNodes::MaterialPreview(OutputSettings::default()),
+            ),
         ]
     }
 }
@@ -1,46 +1,464 @@
-use color_eyre::eyre::Result;
-use std::io::Write;
+use bevy::prelude::Entity;
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+};
 
-use crate::shader::ShaderIO;
+use crate::shader::{
+    BlendMode,
+    ShaderError,
+    ShaderIO,
+    ShaderParameter,
+    TextureSettings,
+    MAX_PARAMETERS,
+    MAX_TEXTURES,
+};
 
 #[derive(Clone)]
 pub struct ShaderBuilder {
+    /// How the material built from the shader blends, set by the output node.
+    pub blend_mode: BlendMode,
     pub content: Vec<String>,
+    pub errors: Vec<ShaderError>,
+    /// Module-level declarations, such as helper functions, structs and constants, emitted once
+    /// between the material bindings and the fragment entry point.
+    pub globals: Vec<String>,
     pub output: ShaderIO,
+    /// Uniforms of the generated `Parameters` struct and their nodes, in binding order.
+    pub parameters: Vec<(NodeRef, ShaderParameter)>,
+    /// Textures sampled by the shader and the Texture node each belongs to, in binding order.
+    pub textures: Vec<(NodeRef, TextureSettings)>,
+    /// The node that declared each variable.
+    pub sources: HashMap<String, NodeRef>,
     pub var: String,
+    /// The statements computed per vertex, if the graph has a vertex stage.
+    pub vertex: Option<VertexStage>,
+    /// The first node whose value can only be computed per fragment, if any.
+    pub fragment_only: Option<NodeRef>,
+}
+
+/// The node a statement is declared for, which names its variables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeRef {
+    /// The node's entity in the editor, or one made up from its ID in a saved graph.
+    pub entity: Entity,
+    pub title: &'static str,
+}
+
+impl NodeRef {
+    /// The name of the variable this node declares for `prefix`.
+    ///
+    /// Entities are unique, so no two nodes share a variable, and the name stays the same for as
+    /// long as the node exists.
+    fn var(&self, prefix: &str) -> String {
+        format!("n{}_{}", self.entity.index(), prefix)
+    }
+}
+
+impl fmt::Display for NodeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.title)
+    }
+}
+
+/// What the generated `vertex` function computes besides Bevy's usual mesh outputs.
+#[derive(Clone, Default)]
+pub struct VertexStage {
+    pub content: Vec<String>,
+    /// A `vec3<f32>` added to the world position of every vertex.
+    pub offset: Option<String>,
+    /// A `vec4<f32>` interpolated across each triangle and read by the fragment stage as
+    /// `custom_varying`, since `varying` is a reserved word in WGSL.
+    pub varying: Option<String>,
 }
 
 impl Default for ShaderBuilder {
     fn default() -> Self {
         Self {
+            blend_mode: BlendMode::default(),
             content: vec![],
+            errors: vec![],
+            globals: vec![],
             output: ShaderIO::Vec4,
+            parameters: vec![],
+            textures: vec![],
+            sources: HashMap::new(),
             var: "ZERO".to_string(),
+            vertex: None,
+            fragment_only: None,
         }
     }
 }
 
-const SHADER_PRELUDE: &str = r#"
-@group(1) @binding(1)
-var texture: texture_2d<f32>;
-@group(1) @binding(2)
-var texture_sampler: sampler;
+/// Declares Bevy's view uniforms, including `globals` with the elapsed time.
+pub const VIEW_BINDINGS: &str = "#import bevy_pbr::mesh_view_bindings";
+
+/// Declares the `mesh` uniform with the model transform of the mesh being drawn.
+pub const MESH_BINDINGS: &str = "#import bevy_pbr::mesh_bindings";
 
+const MESH_FUNCTIONS: &str = "#import bevy_pbr::mesh_functions";
+
+const SHADER_PRELUDE: &str = r#"
 const ZERO = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+"#;
+
+/// The varyings a generated `vertex` function passes on, Bevy's `mesh_vertex_output` followed by
+/// `custom_varying`.
+const VERTEX_OUTPUT: &str = r#"
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    #import bevy_pbr::mesh_vertex_output
+    @location(5) custom_varying: vec4<f32>,
+};
+
+@vertex
+fn vertex(
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+#ifdef VERTEX_TANGENTS
+    @location(3) tangent: vec4<f32>,
+#endif
+#ifdef VERTEX_COLORS
+    @location(4) color: vec4<f32>,
+#endif
+) -> VertexOutput {
+    let world_position = mesh_position_local_to_world(mesh.model, vec4<f32>(position, 1.0));
+    let world_normal = mesh_normal_local_to_world(normal);
+#ifdef VERTEX_TANGENTS
+    let world_tangent = mesh_tangent_local_to_world(mesh.model, tangent);
+#endif
+"#;
+
+/// Passes the mesh attributes on unchanged.
+const VERTEX_VARYINGS: &str = r#"    out.world_normal = world_normal;
+    out.uv = uv;
+#ifdef VERTEX_TANGENTS
+    out.world_tangent = world_tangent;
+#endif
+#ifdef VERTEX_COLORS
+    out.color = color;
+#endif
+"#;
 
+const FRAGMENT_HEADER: &str = r#"
 @fragment
 fn fragment(
+    @builtin(position) frag_coord: vec4<f32>,
     #import bevy_pbr::mesh_vertex_output
-) -> @location(0) vec4<f32> {
 "#;
 
 impl ShaderBuilder {
-    pub fn build(&self) -> Result<String> {
-        let mut buf = Vec::new();
+    /// A scalar literal.
+    pub fn constant(value: f32) -> Self {
+        Self {
+            output: ShaderIO::F32,
+            var: ShaderIO::F32.literal(&[value]),
+            ..Self::default()
+        }
+    }
+
+    /// A builder carrying nothing but `error`, used in place of a value that could not be built.
+    pub fn from_error(error: ShaderError) -> Self {
+        Self {
+            errors: vec![error],
+            ..Self::default()
+        }
+    }
+
+    /// Adds a module-level `declaration` unless an identical one was already added.
+    ///
+    /// Declarations are emitted in the order they were first added, so add anything a
+    /// declaration depends on before it.
+    pub fn add_global(&mut self, declaration: &str) {
+        if !self.globals.iter().any(|global| global == declaration) {
+            self.globals.push(declaration.to_string());
+        }
+    }
+
+    /// Adds the parameter of the Parameter node `node` to the `Parameters` uniform unless it
+    /// already is.
+    ///
+    /// Parameters are looked up by name, so a second node whose name maps to the same field is an
+    /// error.
+    pub fn add_parameter(&mut self, node: NodeRef, parameter: &ShaderParameter) {
+        match self
+            .parameters
+            .iter()
+            .find(|(_, p)| p.field() == parameter.field())
+        {
+            Some((existing, _)) if *existing != node => {
+                let error = ShaderError::DuplicateParameter {
+                    node,
+                    name: parameter.name.clone(),
+                };
+
+                if !self.errors.contains(&error) {
+                    self.errors.push(error);
+                }
+            }
+            Some(_) => {}
+            None => self.parameters.push((node, parameter.clone())),
+        }
+    }
+
+    /// Adds a texture binding for the Texture node `node` unless it already has one, returning
+    /// its name.
+    ///
+    /// Every Texture node gets its own binding, named after the node, and the sampler is named
+    /// after it with a `_sampler` suffix.
+    pub fn add_texture(&mut self, node: NodeRef, settings: &TextureSettings) -> String {
+        if !self.textures.iter().any(|(texture, _)| *texture == node) {
+            self.textures.push((node, settings.clone()));
+        }
+
+        node.var("texture")
+    }
+
+    /// Declares `expression` on behalf of `node` and makes it the builder's value.
+    ///
+    /// The variable is named after `node` and `prefix`, so a node declaring more than one value
+    /// must give each its own prefix. A node reached through several inputs declares the same
+    /// statement each time, which [`ShaderBuilder::merge`] only keeps once.
+    pub fn assign(&mut self, node: NodeRef, prefix: &str, output: ShaderIO, expression: String) {
+        let var = node.var(prefix);
+        let line = format!("let {} = {};", var, expression);
+
+        if !self.content.contains(&line) {
+            self.content.push(line);
+        }
+
+        self.sources.insert(var.clone(), node);
+        self.output = output;
+        self.var = var;
+    }
+
+    /// Declares `expression` like [`ShaderBuilder::assign`] when the shader def `def` is set, and
+    /// `fallback` when it is not.
+    ///
+    /// Used for mesh attributes that only exist on some meshes, such as tangents.
+    pub fn assign_if_def(
+        &mut self,
+        node: NodeRef,
+        prefix: &str,
+        output: ShaderIO,
+        def: &str,
+        expression: String,
+        fallback: String,
+    ) {
+        let var = node.var(prefix);
+        let statement = format!(
+            "#ifdef {def}\nlet {var} = {};\n#else\nlet {var} = {};\n#endif",
+            expression,
+            fallback,
+            def = def,
+            var = var,
+        );
+
+        if !self.content.contains(&statement) {
+            self.content.push(statement);
+        }
+
+        self.sources.insert(var.clone(), node);
+        self.output = output;
+        self.var = var;
+    }
+
+    /// Adds the statements of `other` that are not already in this builder.
+    ///
+    /// Inputs sharing an upstream node carry the same statements for it, so each is only
+    /// declared once.
+    pub fn merge(&mut self, other: &ShaderBuilder) {
+        for line in &other.content {
+            if !self.content.contains(line) {
+                self.content.push(line.clone());
+            }
+        }
+
+        self.fragment_only = self.fragment_only.or(other.fragment_only);
+        self.merge_declarations(other);
+    }
+
+    /// Computes `offset` and `varying` per vertex, adding a vertex stage.
+    ///
+    /// Their statements go to the vertex stage instead of the fragment stage, so neither may
+    /// depend on a fragment-only node.
+    pub fn merge_vertex(
+        &mut self,
+        offset: Option<&ShaderBuilder>,
+        varying: Option<&ShaderBuilder>,
+    ) {
+        let mut stage = self.vertex.take().unwrap_or_default();
+
+        if let Some(offset) = offset {
+            stage.offset = Some(
+                offset
+                    .output
+                    .transform(ShaderIO::Vec3, &offset.var, Some(0.0)),
+            );
+        }
+
+        if let Some(varying) = varying {
+            stage.varying = Some(
+                varying
+                    .output
+                    .transform(ShaderIO::Vec4, &varying.var, Some(0.0)),
+            );
+        }
+
+        for input in offset.into_iter().chain(varying) {
+            if let Some(node) = input.fragment_only {
+                let error = ShaderError::FragmentOnly { node };
+
+                if !self.errors.contains(&error) {
+                    self.errors.push(error);
+                }
+            }
+
+            for line in &input.content {
+                if !stage.content.contains(line) {
+                    stage.content.push(line.clone());
+                }
+            }
+
+            self.merge_declarations(input);
+        }
+
+        self.vertex = Some(stage);
+        self.add_global(VIEW_BINDINGS);
+        self.add_global(MESH_BINDINGS);
+        self.add_global(MESH_FUNCTIONS);
+    }
+
+    /// Adds everything but the fragment statements of `other`.
+    fn merge_declarations(&mut self, other: &ShaderBuilder) {
+        if let Some(vertex) = &other.vertex {
+            let stage = self.vertex.get_or_insert_with(VertexStage::default);
+
+            for line in &vertex.content {
+                if !stage.content.contains(line) {
+                    stage.content.push(line.clone());
+                }
+            }
+
+            stage.offset = stage.offset.take().or_else(|| vertex.offset.clone());
+            stage.varying = stage.varying.take().or_else(|| vertex.varying.clone());
+        }
+
+        for global in &other.globals {
+            self.add_global(global);
+        }
+
+        for (node, parameter) in &other.parameters {
+            self.add_parameter(*node, parameter);
+        }
+
+        for (node, settings) in &other.textures {
+            self.add_texture(*node, settings);
+        }
+
+        for error in &other.errors {
+            if !self.errors.contains(error) {
+                self.errors.push(error.clone());
+            }
+        }
+
+        self.sources
+            .extend(other.sources.iter().map(|(var, node)| (var.clone(), *node)));
+    }
+
+    pub fn build(&self) -> Result<String, ShaderError> {
+        if let Some(error) = self.errors.first() {
+            return Err(error.clone());
+        }
+
+        let mut buf = String::new();
+
+        if self.parameters.len() > MAX_PARAMETERS {
+            return Err(ShaderError::TooManyParameters {
+                max: MAX_PARAMETERS,
+            });
+        }
+
+        if self.textures.len() > MAX_TEXTURES {
+            return Err(ShaderError::TooManyTextures { max: MAX_TEXTURES });
+        }
 
         write!(buf, "{}", SHADER_PRELUDE)?;
 
-        for line in &self.content {
+        for (slot, (node, _)) in self.textures.iter().enumerate() {
+            let name = node.var("texture");
+
+            writeln!(buf, "\n@group(1) @binding({})", 1 + 2 * slot)?;
+            writeln!(buf, "var {}: texture_2d<f32>;", name)?;
+            writeln!(buf, "@group(1) @binding({})", 2 + 2 * slot)?;
+            writeln!(buf, "var {}_sampler: sampler;", name)?;
+        }
+
+        if !self.parameters.is_empty() {
+            writeln!(buf, "\nstruct Parameters {{")?;
+
+            for (_, parameter) in &self.parameters {
+                writeln!(buf, "    {}: vec4<f32>,", parameter.field())?;
+            }
+
+            if self.parameters.len() < MAX_PARAMETERS {
+                writeln!(
+                    buf,
+                    "    _padding: array<vec4<f32>, {}>,",
+                    MAX_PARAMETERS - self.parameters.len()
+                )?;
+            }
+
+            writeln!(buf, "}};\n")?;
+            writeln!(buf, "@group(1) @binding(0)")?;
+            writeln!(buf, "var<uniform> parameters: Parameters;")?;
+        }
+
+        for global in &self.globals {
+            write!(buf, "\n{}\n", global)?;
+        }
+
+        if let Some(vertex) = &self.vertex {
+            write!(buf, "{}", VERTEX_OUTPUT)?;
+
+            for line in vertex
+                .content
+                .iter()
+                .flat_map(|statement| statement.lines())
+            {
+                writeln!(&mut buf, "    {}", line)?;
+            }
+
+            writeln!(&mut buf, "    var out: VertexOutput;")?;
+            writeln!(
+                &mut buf,
+                "    out.world_position = world_position + vec4<f32>({}, 0.0);",
+                vertex.offset.as_deref().unwrap_or("vec3<f32>(0.0)")
+            )?;
+            write!(buf, "{}", VERTEX_VARYINGS)?;
+            writeln!(
+                &mut buf,
+                "    out.custom_varying = {};",
+                vertex.varying.as_deref().unwrap_or("vec4<f32>(0.0)")
+            )?;
+            writeln!(
+                &mut buf,
+                "    out.clip_position = mesh_position_world_to_clip(out.world_position);"
+            )?;
+            writeln!(&mut buf, "    return out;")?;
+            writeln!(&mut buf, "}}")?;
+        }
+
+        write!(buf, "{}", FRAGMENT_HEADER)?;
+
+        if self.vertex.is_some() {
+            writeln!(&mut buf, "    @location(5) custom_varying: vec4<f32>,")?;
+        }
+
+        writeln!(&mut buf, ") -> @location(0) vec4<f32> {{")?;
+
+        for line in self.content.iter().flat_map(|statement| statement.lines()) {
             writeln!(&mut buf, "    {}", line)?;
         }
 
@@ -49,14 +467,230 @@ impl ShaderBuilder {
         } else {
             "0.0"
         };
+        // Vec4 values carry their own alpha, anything narrower is opaque.
+        let color = match self.output {
+            ShaderIO::Vec4 => var.to_string(),
+            io => format!(
+                "vec4<f32>({}, 1.0)",
+                io.transform(ShaderIO::Vec3, var, Some(0.0))
+            ),
+        };
 
-        writeln!(
-            &mut buf,
-            "    return vec4<f32>({}, 1.0);",
-            self.output.transform(ShaderIO::Vec3, var, Some(0.0))
-        )?;
+        writeln!(&mut buf, "    let output_color = {};", color)?;
+
+        if let BlendMode::Mask(cutoff) = self.blend_mode {
+            writeln!(
+                &mut buf,
+                "    if (output_color.a < {}) {{",
+                ShaderIO::F32.literal(&[cutoff])
+            )?;
+            writeln!(&mut buf, "        discard;")?;
+            writeln!(&mut buf, "    }}")?;
+        }
+
+        writeln!(&mut buf, "    return output_color;")?;
         writeln!(&mut buf, "}}")?;
 
-        Ok(String::from_utf8(buf)?)
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{default, Entity};
+    use bevy_flow_node::widgets::NumberInput;
+    use std::collections::HashMap;
+
+    use crate::shader::{
+        ShaderBuilder,
+        ShaderError,
+        ShaderIO,
+        ShaderNodes,
+        ShaderParameter,
+        TextureSettings,
+    };
+
+    /// Resolves `node` as the node of entity `index` with `inputs` connected.
+    fn resolve(
+        index: u32,
+        node: ShaderNodes,
+        inputs: Vec<(&str, ShaderBuilder)>,
+        output: &str,
+    ) -> ShaderBuilder {
+        let inputs = inputs
+            .into_iter()
+            .map(|(input, value)| (input.to_string(), Some(value)))
+            .collect::<HashMap<_, _>>();
+
+        node.resolve_node(Entity::from_raw(index), inputs, Some(output))
+    }
+
+    /// Two Saturate nodes reading one UV component, added together.
+    fn saturated_sum() -> ShaderBuilder {
+        let x = resolve(1, ShaderNodes::UV, vec![], "x");
+        let a = resolve(
+            2,
+            ShaderNodes::Saturate,
+            vec![("value", x.clone())],
+            "saturated",
+        );
+        let b = resolve(3, ShaderNodes::Saturate, vec![("value", x)], "saturated");
+
+        resolve(
+            4,
+            ShaderNodes::Add([0.0; 2]),
+            vec![("a", a), ("b", b)],
+            "result",
+        )
+    }
+
+    #[test]
+    fn nodes_declare_their_own_variables() {
+        let builder = saturated_sum();
+        let shader = builder.build().unwrap();
+
+        assert_eq!(shader.matches("let n1_x = uv.x;").count(), 1);
+        assert!(shader.contains("let n2_saturate = clamp(n1_x, 0.0, 1.0);"));
+        assert!(shader.contains("let n3_saturate = clamp(n1_x, 0.0, 1.0);"));
+        assert!(shader.contains("let n4_add = n2_saturate + n3_saturate;"));
+        assert_eq!(builder.validate(&shader), Ok(()));
+    }
+
+    #[test]
+    fn texture_nodes_get_their_own_bindings() {
+        let texture = ShaderNodes::Texture(TextureSettings {
+            path: Some("image.png".to_string()),
+            ..default()
+        });
+        let a = resolve(1, texture.clone(), vec![], "color");
+        let b = resolve(2, texture, vec![], "color");
+        let builder = resolve(3, ShaderNodes::Mix, vec![("a", a), ("b", b)], "result");
+        let shader = builder.build().unwrap();
+
+        assert!(shader.contains("@group(1) @binding(1)\nvar n1_texture: texture_2d<f32>;"));
+        assert!(shader.contains("@group(1) @binding(4)\nvar n2_texture_sampler: sampler;"));
+        assert_eq!(builder.validate(&shader), Ok(()));
+    }
+
+    #[test]
+    fn arithmetic_inputs_have_their_own_defaults() {
+        let a = resolve(1, ShaderNodes::UV, vec![], "x");
+        let unconnected = resolve(2, ShaderNodes::Subtract([2.0, 0.5]), vec![], "result");
+        let connected = resolve(
+            2,
+            ShaderNodes::Subtract([2.0, 0.5]),
+            vec![("a", a)],
+            "result",
+        );
+
+        assert!(unconnected
+            .build()
+            .unwrap()
+            .contains("let n2_subtract = 2.0 - 0.5;"));
+        assert!(connected
+            .build()
+            .unwrap()
+            .contains("let n2_subtract = n1_x - 0.5;"));
+    }
+
+    #[test]
+    fn unconnected_noise_seeds_use_their_default() {
+        let builder = resolve(1, ShaderNodes::ValueNoise([8.0, 3.0]), vec![], "noise");
+        let shader = builder.build().unwrap();
+
+        assert!(shader.contains("value_noise(uv * 8.0 + 3.0 * vec2<f32>(12.9898, 78.233))"));
+        assert_eq!(builder.validate(&shader), Ok(()));
+    }
+
+    #[test]
+    fn extend_truncates_vec4_inputs() {
+        let vector = resolve(1, ShaderNodes::Vector, vec![], "vec");
+        let builder = resolve(
+            2,
+            ShaderNodes::Extend(NumberInput { value: 0.5 }),
+            vec![("value", vector)],
+            "vec",
+        );
+        let shader = builder.build().unwrap();
+
+        assert!(shader.contains("let n2_extend = vec4<f32>(n1_vec.xyz, 0.5);"));
+        assert_eq!(builder.validate(&shader), Ok(()));
+    }
+
+    #[test]
+    fn texture_lod_and_bias_are_exclusive() {
+        let texture = ShaderNodes::Texture(TextureSettings {
+            path: Some("image.png".to_string()),
+            ..default()
+        });
+        let x = resolve(1, ShaderNodes::UV, vec![], "x");
+        let builder = resolve(2, texture, vec![("lod", x.clone()), ("bias", x)], "color");
+
+        assert!(matches!(
+            builder.build(),
+            Err(ShaderError::ExclusiveInputs { node, a: "lod", b: "bias" })
+                if node.entity == Entity::from_raw(2)
+        ));
+    }
+
+    #[test]
+    fn errors_name_their_node() {
+        let builder = resolve(7, ShaderNodes::Length, vec![], "result");
+        let node = builder.build().unwrap_err().node().unwrap();
+
+        assert_eq!(node.entity, Entity::from_raw(7));
+        assert_eq!(node.title, "Length");
+    }
+
+    #[test]
+    fn output_is_the_same_on_every_build() {
+        assert_eq!(saturated_sum().build(), saturated_sum().build());
+    }
+
+    #[test]
+    fn parameters_need_their_own_names() {
+        let parameter = |index, name| {
+            let parameter = ShaderParameter::new(name, ShaderIO::F32, [0.5; 4]);
+
+            resolve(index, ShaderNodes::Parameter(parameter), vec![], "value")
+        };
+        let a = parameter(1, "speed");
+        let distinct = resolve(
+            3,
+            ShaderNodes::Add([0.0; 2]),
+            vec![("a", a.clone()), ("b", parameter(2, "scale"))],
+            "result",
+        );
+        let reused = resolve(
+            3,
+            ShaderNodes::Add([0.0; 2]),
+            vec![("a", a.clone()), ("b", a.clone())],
+            "result",
+        );
+        let duplicate = resolve(
+            3,
+            ShaderNodes::Add([0.0; 2]),
+            vec![("a", a), ("b", parameter(2, "speed"))],
+            "result",
+        );
+
+        assert_eq!(distinct.parameters.len(), 2);
+        assert!(distinct.build().is_ok());
+        assert_eq!(reused.parameters.len(), 1);
+        assert!(reused.build().is_ok());
+        assert!(matches!(
+            duplicate.build(),
+            Err(ShaderError::DuplicateParameter { node, .. }) if node.entity == Entity::from_raw(2)
+        ));
+    }
+
+    #[test]
+    fn keyword_parameter_names_are_escaped() {
+        let parameter = ShaderParameter::new("vec2", ShaderIO::Vec2, [0.5; 4]);
+        let builder = resolve(1, ShaderNodes::Parameter(parameter), vec![], "value");
+        let shader = builder.build().unwrap();
+
+        assert!(shader.contains("    p_vec2: vec4<f32>,"));
+        assert_eq!(builder.validate(&shader), Ok(()));
     }
 }
@@ -0,0 +1,119 @@
+use std::{error::Error, fmt, io};
+
+use crate::shader::NodeRef;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShaderError {
+    /// A node with several outputs was resolved without naming one, or with an unknown label.
+    MissingOutput {
+        node: NodeRef,
+        output: Option<String>,
+    },
+    /// A node needs a value on `input` but nothing is connected to it.
+    UnconnectedInput {
+        node: NodeRef,
+        input: &'static str,
+    },
+    /// A node has values connected to both `a` and `b` but can only use one of them.
+    ExclusiveInputs {
+        node: NodeRef,
+        a: &'static str,
+        b: &'static str,
+    },
+    /// The graph samples more textures than the material has room for.
+    TooManyTextures {
+        max: usize,
+    },
+    /// A Parameter node is named `name` like another one in the graph.
+    DuplicateParameter {
+        node: NodeRef,
+        name: String,
+    },
+    /// A node that only works per fragment, such as a texture sample, feeds the vertex stage.
+    FragmentOnly {
+        node: NodeRef,
+    },
+    /// The graph uses more parameters than the material has room for.
+    TooManyParameters {
+        max: usize,
+    },
+    /// naga rejected the generated WGSL at `line`, which was declared by `node` if known.
+    Invalid {
+        node: Option<NodeRef>,
+        line: usize,
+        message: String,
+    },
+    Io(String),
+}
+
+impl ShaderError {
+    /// The node the error should be shown on, if it comes from one.
+    pub fn node(&self) -> Option<NodeRef> {
+        match self {
+            Self::MissingOutput { node, .. }
+            | Self::UnconnectedInput { node, .. }
+            | Self::ExclusiveInputs { node, .. }
+            | Self::DuplicateParameter { node, .. }
+            | Self::FragmentOnly { node } => Some(*node),
+            Self::Invalid { node, .. } => *node,
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingOutput {
+                node,
+                output: Some(output),
+            } => write!(f, "{}: unknown output '{}'", node, output),
+            Self::MissingOutput { node, output: None } => {
+                write!(f, "{}: no output was requested", node)
+            }
+            Self::UnconnectedInput { node, input } => {
+                write!(f, "{}: input '{}' is not connected", node, input)
+            }
+            Self::ExclusiveInputs { node, a, b } => {
+                write!(f, "{}: '{}' and '{}' cannot both be connected", node, a, b)
+            }
+            Self::DuplicateParameter { node, name } => {
+                write!(f, "{}: another parameter is named '{}'", node, name)
+            }
+            Self::FragmentOnly { node } => {
+                write!(f, "{}: cannot be used in the vertex stage", node)
+            }
+            Self::TooManyTextures { max } => {
+                write!(f, "graph uses more than {} textures", max)
+            }
+            Self::TooManyParameters { max } => {
+                write!(f, "graph uses more than {} parameters", max)
+            }
+            Self::Invalid {
+                node: Some(node),
+                line,
+                message,
+            } => write!(f, "{}: invalid WGSL at line {}: {}", node, line, message),
+            Self::Invalid {
+                node: None,
+                line,
+                message,
+            } => write!(f, "invalid WGSL at line {}: {}", line, message),
+            Self::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for ShaderError {}
+
+impl From<io::Error> for ShaderError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e.to_string())
+    }
+}
+
+impl From<fmt::Error> for ShaderError {
+    fn from(e: fmt::Error) -> Self {
+        Self::Io(e.to_string())
+    }
+}
@@ -1,4 +1,8 @@
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize,
+)]
 pub enum ShaderIO {
     #[default]
     F32,
@@ -8,6 +12,13 @@ pub enum ShaderIO {
 }
 
 impl ShaderIO {
+    pub const ALL: [ShaderIO; 4] = [
+        ShaderIO::F32,
+        ShaderIO::Vec2,
+        ShaderIO::Vec3,
+        ShaderIO::Vec4,
+    ];
+
     pub fn extend(self) -> Self {
         match self {
             ShaderIO::F32 => ShaderIO::Vec2,
@@ -16,31 +27,105 @@ impl ShaderIO {
             ShaderIO::Vec4 => ShaderIO::Vec4,
         }
     }
+
+    /// Number of `f32` components in a value of this type.
+    pub fn components(self) -> usize {
+        match self {
+            ShaderIO::F32 => 1,
+            ShaderIO::Vec2 => 2,
+            ShaderIO::Vec3 => 3,
+            ShaderIO::Vec4 => 4,
+        }
+    }
+
     pub fn fill(&self, value: f32) -> String {
         match self {
-            ShaderIO::F32 => format!("{}", value),
-            ShaderIO::Vec2 => format!("vec2<f32>({:.5})", value),
-            ShaderIO::Vec3 => format!("vec3<f32>({:.5})", value),
-            ShaderIO::Vec4 => format!("vec4<f32>({:.5})", value),
+            ShaderIO::F32 => float(value),
+            ShaderIO::Vec2 => format!("vec2<f32>({})", float(value)),
+            ShaderIO::Vec3 => format!("vec3<f32>({})", float(value)),
+            ShaderIO::Vec4 => format!("vec4<f32>({})", float(value)),
+        }
+    }
+
+    /// A literal of this type from the leading components of `values`.
+    pub fn literal(&self, values: &[f32]) -> String {
+        let components = values[..self.components()]
+            .iter()
+            .map(|value| float(*value))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        match self {
+            ShaderIO::F32 => components,
+            ShaderIO::Vec2 => format!("vec2<f32>({})", components),
+            ShaderIO::Vec3 => format!("vec3<f32>({})", components),
+            ShaderIO::Vec4 => format!("vec4<f32>({})", components),
+        }
+    }
+
+    /// Converts `var` to the wider `target` type for use alongside a value of that type.
+    ///
+    /// Scalars are splatted across every component, vectors are padded by [`ShaderIO::transform`].
+    pub fn promote(self, target: ShaderIO, var: &str) -> String {
+        match (self, target) {
+            (Self::F32, Self::Vec2) => format!("vec2<f32>({})", var),
+            (Self::F32, Self::Vec3) => format!("vec3<f32>({})", var),
+            (Self::F32, Self::Vec4) => format!("vec4<f32>({})", var),
+            _ => self.transform(target, var, None),
         }
     }
     pub fn transform(self, target: ShaderIO, var: &str, extend: Option<f32>) -> String {
-        let extend = extend.unwrap_or(0.0);
+        let extend = float(extend.unwrap_or(0.0));
 
         match (self, target) {
             (Self::F32, Self::F32) => var.to_string(),
-            (Self::F32, Self::Vec2) => format!("vec2<f32>({}, {:.5})", var, extend),
-            (Self::F32, Self::Vec3) => format!("vec3<f32>({}, vec2<f32>({:.5}))", var, extend),
-            (Self::F32, Self::Vec4) => format!("vec4<f32>({}, vec3<f32>({:.5}))", var, extend),
+            (Self::F32, Self::Vec2) => format!("vec2<f32>({}, {})", var, extend),
+            (Self::F32, Self::Vec3) => format!("vec3<f32>({}, vec2<f32>({}))", var, extend),
+            (Self::F32, Self::Vec4) => format!("vec4<f32>({}, vec3<f32>({}))", var, extend),
             (_, Self::F32) => format!("{}.x", var),
             (Self::Vec2, Self::Vec2) => var.to_string(),
-            (Self::Vec2, Self::Vec3) => format!("vec3<f32>({}, {:.5})", var, extend),
-            (Self::Vec2, Self::Vec4) => format!("vec4<f32>({}, vec2<f32>({:.5}))", var, extend),
+            (Self::Vec2, Self::Vec3) => format!("vec3<f32>({}, {})", var, extend),
+            (Self::Vec2, Self::Vec4) => format!("vec4<f32>({}, vec2<f32>({}))", var, extend),
             (_, Self::Vec2) => format!("{}.xy", var),
             (Self::Vec3, Self::Vec3) => var.to_string(),
-            (Self::Vec3, Self::Vec4) => format!("vec4<f32>({}, {:.5})", var, extend),
+            (Self::Vec3, Self::Vec4) => format!("vec4<f32>({}, {})", var, extend),
             (_, Self::Vec3) => format!("{}.xyz", var),
             _ => var.to_string(),
         }
     }
 }
+
+/// `value` as a WGSL float literal that reads back as the same `f32`.
+///
+/// Debug formatting gives the shortest such text and always keeps a decimal point or exponent, so
+/// whole numbers don't turn into integer literals. WGSL has no literal for infinity or NaN, which
+/// become the largest float and zero.
+fn float(value: f32) -> String {
+    let value = match value {
+        value if value.is_nan() => 0.0,
+        value => value.clamp(f32::MIN, f32::MAX),
+    };
+
+    format!("{:?}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn floats_are_exact_wgsl_literals() {
+        assert_eq!(ShaderIO::F32.literal(&[1.0]), "1.0");
+        assert_eq!(ShaderIO::F32.literal(&[-0.1]), "-0.1");
+        assert_eq!(ShaderIO::F32.literal(&[1.0e-7]), "1e-7");
+        assert_eq!(ShaderIO::Vec2.fill(f32::NAN), "vec2<f32>(0.0)");
+        assert_eq!(
+            ShaderIO::Vec3.literal(&[0.000_012_5, 2.0, 3.0]),
+            "vec3<f32>(1.25e-5, 2.0, 3.0)"
+        );
+
+        let max = ShaderIO::F32.literal(&[f32::INFINITY]);
+
+        assert_eq!(max.parse::<f32>(), Ok(f32::MAX));
+    }
+}
@@ -0,0 +1,70 @@
+/// The `bevy_pbr` modules [`PBR_LIGHTING`] calls into, in dependency order.
+///
+/// Add [`VIEW_BINDINGS`](crate::shader::VIEW_BINDINGS) and
+/// [`MESH_BINDINGS`](crate::shader::MESH_BINDINGS) before these.
+pub const PBR_IMPORTS: &str = r#"#import bevy_pbr::pbr_types
+#import bevy_pbr::utils
+#import bevy_pbr::clustered_forward
+#import bevy_pbr::lighting
+#import bevy_pbr::pbr_ambient
+#import bevy_pbr::shadows
+#import bevy_pbr::fog
+#import bevy_pbr::pbr_functions"#;
+
+/// Returns how much a surface faces away from the viewer, rising towards 1 at grazing angles.
+pub const FRESNEL: &str = r#"fn fresnel(normal: vec3<f32>, view_direction: vec3<f32>, power: f32) -> f32 {
+    let facing = clamp(dot(normalize(normal), normalize(view_direction)), 0.0, 1.0);
+    return pow(1.0 - facing, power);
+}"#;
+
+/// Lights a surface the same way `StandardMaterial` does, keeping the base color's alpha.
+///
+/// Follows `bevy_pbr`'s own `pbr.wgsl` fragment, including its fog, tone mapping, dithering and
+/// alpha premultiplication, minus the texture sampling the graph does itself.
+pub const PBR_LIGHTING: &str = r#"fn pbr_lighting(
+    base_color: vec4<f32>,
+    metallic: f32,
+    roughness: f32,
+    emissive: vec3<f32>,
+    normal: vec3<f32>,
+    occlusion: f32,
+    frag_coord: vec4<f32>,
+    world_position: vec4<f32>,
+    world_normal: vec3<f32>,
+) -> vec4<f32> {
+    var pbr_input: PbrInput = pbr_input_new();
+    pbr_input.material.base_color = base_color;
+    pbr_input.material.metallic = metallic;
+    pbr_input.material.perceptual_roughness = roughness;
+    pbr_input.material.emissive = vec4<f32>(emissive, 1.0);
+    pbr_input.occlusion = occlusion;
+    pbr_input.frag_coord = frag_coord;
+    pbr_input.world_position = world_position;
+    pbr_input.world_normal = world_normal;
+    pbr_input.is_orthographic = view.projection[3].w == 1.0;
+    pbr_input.N = normalize(normal);
+    pbr_input.V = calculate_view(world_position, pbr_input.is_orthographic);
+    pbr_input.flags = mesh.flags;
+    // Blending keeps the alpha as is, the output node applies its own blend mode.
+    pbr_input.material.flags = STANDARD_MATERIAL_FLAGS_ALPHA_MODE_BLEND;
+
+    var color = pbr(pbr_input);
+
+    if (fog.mode != FOG_MODE_OFF) {
+        color = apply_fog(color, world_position.xyz, view.world_position.xyz);
+    }
+#ifdef TONEMAP_IN_SHADER
+    color = tone_mapping(color);
+#ifdef DEBAND_DITHER
+    var color_rgb = color.rgb;
+    color_rgb = powsafe(color_rgb, 1.0 / 2.2);
+    color_rgb = color_rgb + screen_space_dither(frag_coord.xy);
+    color_rgb = powsafe(color_rgb, 2.2);
+    color = vec4<f32>(color_rgb, color.a);
+#endif
+#endif
+#ifdef PREMULTIPLY_ALPHA
+    color = premultiply_alpha(pbr_input.material.flags, color);
+#endif
+    return color;
+}"#;
@@ -1,7 +1,18 @@
 mod builder;
+mod error;
 mod io;
+mod lighting;
 mod nodes;
+mod noise;
+mod output;
+mod parameter;
+mod texture;
+mod validate;
 
-pub use builder::ShaderBuilder;
+pub use builder::{NodeRef, ShaderBuilder, MESH_BINDINGS, VIEW_BINDINGS};
+pub use error::ShaderError;
 pub use io::ShaderIO;
 pub use nodes::ShaderNodes;
+pub use output::{BlendMode, Lighting, OutputSettings};
+pub use parameter::{ShaderParameter, MAX_PARAMETERS};
+pub use texture::{AddressMode, FilterMode, TextureSettings, MAX_TEXTURES};
@@ -7,186 +7,1006 @@ use bevy_flow_node::{
     FlowNodeSlot,
     FlowNodeTemplate,
 };
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-use crate::shader::{ShaderBuilder, ShaderIO};
+use crate::shader::{
+    lighting::{FRESNEL, PBR_IMPORTS, PBR_LIGHTING},
+    noise::{NOISE_HASH, PERLIN_NOISE, SIMPLEX_NOISE, VALUE_NOISE, VORONOI},
+    Lighting,
+    NodeRef,
+    OutputSettings,
+    ShaderBuilder,
+    ShaderError,
+    ShaderIO,
+    ShaderParameter,
+    TextureSettings,
+    MESH_BINDINGS,
+    VIEW_BINDINGS,
+};
 
-#[derive(Clone, Default, PartialEq)]
+#[derive(Clone, Default, PartialEq, Deserialize, Serialize)]
 pub enum ShaderNodes {
+    Add([f32; 2]),
+    Bitangent,
+    Color([f32; 4]),
     Component,
-    Extend(NumberInput),
-    MaterialPreview,
+    Cross,
+    Distance,
+    Divide([f32; 2]),
+    Dot,
+    Extend(#[serde(with = "number_input")] NumberInput),
+    Float(#[serde(with = "number_input")] NumberInput),
+    Fresnel(#[serde(with = "number_input")] NumberInput),
+    Length,
+    MaterialPreview(OutputSettings),
+    Mix,
+    Modulo([f32; 2]),
+    Multiply([f32; 2]),
     Normal,
+    Normalize,
+    Parameter(ShaderParameter),
+    PerlinNoise([f32; 2]),
+    Position,
+    Power([f32; 2]),
     #[default]
     Print,
+    Reflect,
     Saturate,
-    Texture,
+    SimplexNoise([f32; 2]),
+    Smoothstep,
+    Step,
+    Subtract([f32; 2]),
+    Tangent,
+    Texture(TextureSettings),
+    Time,
     UV,
+    ValueNoise([f32; 2]),
+    Varying,
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
     Vector,
+    VertexColor,
+    ViewDirection,
+    Voronoi([f32; 2]),
 }
 
-impl FlowNodeSet for ShaderNodes {
-    type NodeIO = ShaderBuilder;
+impl ShaderNodes {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Self::Add(_) => "Add",
+            Self::Bitangent => "Bitangent",
+            Self::Color(_) => "Color",
+            Self::Component => "Component",
+            Self::Cross => "Cross",
+            Self::Distance => "Distance",
+            Self::Divide(_) => "Divide",
+            Self::Dot => "Dot",
+            Self::Extend(_) => "Extend",
+            Self::Float(_) => "Float",
+            Self::Fresnel(_) => "Fresnel",
+            Self::Length => "Length",
+            Self::MaterialPreview(_) => "Preview",
+            Self::Mix => "Mix",
+            Self::Modulo(_) => "Modulo",
+            Self::Multiply(_) => "Multiply",
+            Self::Normal => "Normal",
+            Self::Normalize => "Normalize",
+            Self::Parameter(_) => "Parameter",
+            Self::PerlinNoise(_) => "Perlin Noise",
+            Self::Position => "Position",
+            Self::Power(_) => "Power",
+            Self::Print => "Print",
+            Self::Reflect => "Reflect",
+            Self::Saturate => "Saturate",
+            Self::SimplexNoise(_) => "Simplex Noise",
+            Self::Smoothstep => "Smoothstep",
+            Self::Step => "Step",
+            Self::Subtract(_) => "Subtract",
+            Self::Tangent => "Tangent",
+            Self::Texture(_) => "Texture",
+            Self::Time => "Time",
+            Self::UV => "UV",
+            Self::ValueNoise(_) => "Value Noise",
+            Self::Varying => "Varying",
+            Self::Vec2(_) => "Vec2",
+            Self::Vec3(_) => "Vec3",
+            Self::Vec4(_) => "Vec4",
+            Self::Vector => "Vector",
+            Self::VertexColor => "Vertex Color",
+            Self::ViewDirection => "View Direction",
+            Self::Voronoi(_) => "Voronoi",
+        }
+    }
 
-    fn resolve(
+    /// The node's inputs in socket order, each with the types it takes without converting them.
+    ///
+    /// Wider values are truncated to the widest of these, narrower ones are padded or splatted.
+    pub fn inputs(&self) -> &'static [(&'static str, &'static [ShaderIO])] {
+        const ANY: &[ShaderIO] = &ShaderIO::ALL;
+        const SCALAR: &[ShaderIO] = &[ShaderIO::F32];
+        const VEC2: &[ShaderIO] = &[ShaderIO::Vec2];
+        const VEC3: &[ShaderIO] = &[ShaderIO::Vec3];
+        const UP_TO_VEC3: &[ShaderIO] = &[ShaderIO::F32, ShaderIO::Vec2, ShaderIO::Vec3];
+
+        match self {
+            Self::Add(_)
+            | Self::Distance
+            | Self::Divide(_)
+            | Self::Dot
+            | Self::Modulo(_)
+            | Self::Multiply(_)
+            | Self::Power(_)
+            | Self::Subtract(_) => &[("a", ANY), ("b", ANY)],
+            Self::Component | Self::Length | Self::Normalize | Self::Saturate => &[("value", ANY)],
+            Self::Cross => &[("a", UP_TO_VEC3), ("b", UP_TO_VEC3)],
+            Self::Extend(_) => &[("value", UP_TO_VEC3)],
+            Self::Fresnel(_) => &[("normal", VEC3), ("view", VEC3), ("power", SCALAR)],
+            Self::MaterialPreview(OutputSettings {
+                lighting: Lighting::Unlit,
+                ..
+            }) => &[
+                ("color", ANY),
+                ("alpha", SCALAR),
+                ("position_offset", VEC3),
+                ("varying", ANY),
+            ],
+            Self::MaterialPreview(OutputSettings {
+                lighting: Lighting::Pbr,
+                ..
+            }) => &[
+                ("base_color", ANY),
+                ("alpha", SCALAR),
+                ("metallic", SCALAR),
+                ("roughness", SCALAR),
+                ("emissive", VEC3),
+                ("normal", VEC3),
+                ("occlusion", SCALAR),
+                ("position_offset", VEC3),
+                ("varying", ANY),
+            ],
+            Self::Mix => &[("a", ANY), ("b", ANY), ("t", ANY)],
+            Self::PerlinNoise(_)
+            | Self::SimplexNoise(_)
+            | Self::ValueNoise(_)
+            | Self::Voronoi(_) => &[("uv", VEC2), ("scale", SCALAR), ("seed", SCALAR)],
+            Self::Print => &[("output", ANY)],
+            Self::Reflect => &[("incident", ANY), ("normal", ANY)],
+            Self::Smoothstep => &[("edge0", ANY), ("edge1", ANY), ("x", ANY)],
+            Self::Step => &[("edge", ANY), ("x", ANY)],
+            Self::Texture(_) => &[("uv", VEC2), ("lod", SCALAR), ("bias", SCALAR)],
+            Self::Vector => &[("x", SCALAR), ("y", SCALAR), ("z", SCALAR), ("w", SCALAR)],
+            _ => &[],
+        }
+    }
+
+    /// The input of this node that takes over the link into `input` of the node it was rebuilt
+    /// from, such as the main color of a preview switched between lighting modes.
+    pub fn rebuilt_input<'a>(&self, input: &'a str) -> &'a str {
+        match (self, input) {
+            (
+                Self::MaterialPreview(OutputSettings {
+                    lighting: Lighting::Pbr,
+                    ..
+                }),
+                "color",
+            ) => "base_color",
+            (
+                Self::MaterialPreview(OutputSettings {
+                    lighting: Lighting::Unlit,
+                    ..
+                }),
+                "base_color",
+            ) => "color",
+            _ => input,
+        }
+    }
+
+    /// The types `input` takes without converting them, every type for unknown inputs.
+    pub fn input_types(&self, input: &str) -> &'static [ShaderIO] {
+        self.inputs()
+            .iter()
+            .find(|(label, _)| *label == input)
+            .map_or(&ShaderIO::ALL, |(_, types)| *types)
+    }
+
+    /// The types `output` can have, every type for outputs that follow their inputs.
+    pub fn output_types(&self, output: &str) -> &[ShaderIO] {
+        match (self, output) {
+            (Self::Color(_), "color")
+            | (Self::Texture(_), "color")
+            | (Self::Varying, _)
+            | (Self::Vec4(_), _)
+            | (Self::Vector, _)
+            | (Self::VertexColor, _) => &[ShaderIO::Vec4],
+            (Self::Bitangent, _)
+            | (Self::Color(_), "rgb")
+            | (Self::Cross, _)
+            | (Self::Normal, "normal")
+            | (Self::Position, _)
+            | (Self::Tangent, _)
+            | (Self::Vec3(_), _)
+            | (Self::ViewDirection, _) => &[ShaderIO::Vec3],
+            (Self::UV, "uv") | (Self::Vec2(_), _) => &[ShaderIO::Vec2],
+            (Self::Extend(_), _) => &[ShaderIO::Vec2, ShaderIO::Vec3, ShaderIO::Vec4],
+            (Self::Parameter(parameter), _) => std::slice::from_ref(&parameter.io),
+            (
+                Self::Add(_)
+                | Self::Divide(_)
+                | Self::Mix
+                | Self::Modulo(_)
+                | Self::Multiply(_)
+                | Self::Normalize
+                | Self::Power(_)
+                | Self::Reflect
+                | Self::Saturate
+                | Self::Smoothstep
+                | Self::Step
+                | Self::Subtract(_),
+                _,
+            ) => &ShaderIO::ALL,
+            _ => &[ShaderIO::F32],
+        }
+    }
+}
+
+/// Calls the WGSL builtin `function`, promoting every argument to the widest argument type.
+fn call(node: NodeRef, function: &str, args: &[ShaderBuilder]) -> ShaderBuilder {
+    call_returning(node, function, args, None)
+}
+
+/// Calls `function` like [`call`], for builtins whose result is `output` instead of the argument
+/// type.
+fn call_returning(
+    node: NodeRef,
+    function: &str,
+    args: &[ShaderBuilder],
+    output: Option<ShaderIO>,
+) -> ShaderBuilder {
+    let io = args.iter().map(|arg| arg.output).max().unwrap_or_default();
+    let vars = args
+        .iter()
+        .map(|arg| arg.output.promote(io, &arg.var))
+        .collect::<Vec<_>>();
+    let mut builder = ShaderBuilder::default();
+
+    for arg in args {
+        builder.merge(arg);
+    }
+
+    builder.assign(
+        node,
+        function,
+        output.unwrap_or(io),
+        format!("{}({})", function, vars.join(", ")),
+    );
+
+    builder
+}
+
+/// Takes `input` as a color, with its alpha replaced by the `alpha` input if that is connected.
+///
+/// Values without an alpha of their own are opaque.
+fn color_input(
+    node: NodeRef,
+    inputs: &mut HashMap<String, Option<ShaderBuilder>>,
+    input: &str,
+    default: ShaderBuilder,
+) -> ShaderBuilder {
+    let mut color = inputs.remove(input).flatten().unwrap_or(default);
+    let alpha = inputs.remove("alpha").flatten();
+    let alpha_var = match &alpha {
+        Some(alpha) => alpha.output.transform(ShaderIO::F32, &alpha.var, None),
+        None if color.output == ShaderIO::Vec4 => return color,
+        None => "1.0".to_string(),
+    };
+    let expression = format!(
+        "vec4<f32>({}, {})",
+        color
+            .output
+            .transform(ShaderIO::Vec3, &color.var, Some(0.0)),
+        alpha_var,
+    );
+
+    if let Some(alpha) = &alpha {
+        color.merge(alpha);
+    }
+
+    color.assign(node, "color", ShaderIO::Vec4, expression);
+    color
+}
+
+/// Lights the surface described by the PBR output's inputs with `bevy_pbr`.
+fn pbr_color(node: NodeRef, inputs: &mut HashMap<String, Option<ShaderBuilder>>) -> ShaderBuilder {
+    let white = ShaderBuilder {
+        output: ShaderIO::Vec4,
+        var: ShaderIO::Vec4.fill(1.0),
+        ..default()
+    };
+    let base_color = color_input(node, inputs, "base_color", white);
+    let metallic = input_or(inputs, "metallic", 0.0);
+    let roughness = input_or(inputs, "roughness", 0.5);
+    let emissive = inputs
+        .remove("emissive")
+        .flatten()
+        .unwrap_or_else(|| ShaderBuilder {
+            output: ShaderIO::Vec3,
+            var: ShaderIO::Vec3.fill(0.0),
+            ..default()
+        });
+    let normal = inputs
+        .remove("normal")
+        .flatten()
+        .unwrap_or_else(|| ShaderBuilder {
+            output: ShaderIO::Vec3,
+            var: "world_normal".to_string(),
+            ..default()
+        });
+    let occlusion = input_or(inputs, "occlusion", 1.0);
+    let args = [
+        base_color.var.clone(),
+        metallic
+            .output
+            .transform(ShaderIO::F32, &metallic.var, None),
+        roughness
+            .output
+            .transform(ShaderIO::F32, &roughness.var, None),
+        emissive.output.promote(ShaderIO::Vec3, &emissive.var),
+        normal.output.promote(ShaderIO::Vec3, &normal.var),
+        occlusion
+            .output
+            .transform(ShaderIO::F32, &occlusion.var, None),
+    ];
+    let mut builder = ShaderBuilder::default();
+
+    for input in [
+        &base_color,
+        &metallic,
+        &roughness,
+        &emissive,
+        &normal,
+        &occlusion,
+    ] {
+        builder.merge(input);
+    }
+
+    builder.add_global(VIEW_BINDINGS);
+    builder.add_global(MESH_BINDINGS);
+    builder.add_global(PBR_IMPORTS);
+    builder.add_global(PBR_LIGHTING);
+    builder.assign(
+        node,
+        "pbr",
+        ShaderIO::Vec4,
+        format!(
+            "pbr_lighting({}, frag_coord, world_position, world_normal)",
+            args.join(", ")
+        ),
+    );
+
+    builder
+}
+
+/// The mesh's world space tangent with the bitangent sign in `w`, or one made up from the normal
+/// on meshes without tangents.
+fn world_tangent(node: NodeRef) -> ShaderBuilder {
+    let mut builder = ShaderBuilder::default();
+
+    builder.assign_if_def(
+        node,
+        "world_tangent",
+        ShaderIO::Vec4,
+        "VERTEX_TANGENTS",
+        "world_tangent".to_string(),
+        "vec4<f32>(normalize(cross(select(vec3<f32>(0.0, 1.0, 0.0), vec3<f32>(1.0, 0.0, 0.0), \
+         abs(world_normal.y) > 0.99), world_normal)), 1.0)"
+            .to_string(),
+    );
+
+    builder
+}
+
+/// The direction from the fragment towards the camera, in world space.
+fn view_direction(node: NodeRef) -> ShaderBuilder {
+    let mut builder = ShaderBuilder::default();
+
+    builder.add_global(VIEW_BINDINGS);
+    builder.assign(
+        node,
+        "view_direction",
+        ShaderIO::Vec3,
+        "normalize(view.world_position - world_position.xyz)".to_string(),
+    );
+
+    builder
+}
+
+/// Checks that `output` is one of `labels`.
+fn output_label<'a>(
+    node: NodeRef,
+    output: Option<&'a str>,
+    labels: &[&str],
+) -> Result<&'a str, ShaderError> {
+    match output {
+        Some(output) if labels.contains(&output) => Ok(output),
+        _ => Err(ShaderError::MissingOutput {
+            node,
+            output: output.map(str::to_string),
+        }),
+    }
+}
+
+/// Takes the value connected to `input`, or an error if nothing is connected.
+fn required_input(
+    node: NodeRef,
+    inputs: &mut HashMap<String, Option<ShaderBuilder>>,
+    input: &'static str,
+) -> ShaderBuilder {
+    inputs
+        .remove(input)
+        .flatten()
+        .unwrap_or_else(|| ShaderBuilder::from_error(ShaderError::UnconnectedInput { node, input }))
+}
+
+fn optional_input(
+    inputs: &mut HashMap<String, Option<ShaderBuilder>>,
+    input: &str,
+) -> ShaderBuilder {
+    inputs.remove(input).flatten().unwrap_or_default()
+}
+
+/// Takes the value connected to `uv`, or the mesh UVs if nothing is connected.
+fn uv_input(inputs: &mut HashMap<String, Option<ShaderBuilder>>) -> ShaderBuilder {
+    inputs
+        .remove("uv")
+        .flatten()
+        .unwrap_or_else(|| ShaderBuilder {
+            output: ShaderIO::Vec2,
+            var: "uv".to_string(),
+            ..default()
+        })
+}
+
+/// Takes the value connected to `input`, or the constant `default` if nothing is connected.
+fn input_or(
+    inputs: &mut HashMap<String, Option<ShaderBuilder>>,
+    input: &str,
+    default: f32,
+) -> ShaderBuilder {
+    inputs
+        .remove(input)
+        .flatten()
+        .unwrap_or_else(|| ShaderBuilder::constant(default))
+}
+
+impl ShaderNodes {
+    /// Resolves this node, the node of `entity`, from the values connected to its inputs.
+    ///
+    /// Variables are named after `entity`, so it must be unique within the graph.
+    pub fn resolve_node(
         &self,
-        inputs: HashMap<String, Option<Self::NodeIO>>,
+        entity: Entity,
+        inputs: HashMap<String, Option<ShaderBuilder>>,
         output: Option<&str>,
-    ) -> Self::NodeIO {
+    ) -> ShaderBuilder {
+        let node = NodeRef {
+            entity,
+            title: self.title(),
+        };
         let mut inputs = inputs;
 
         match self {
+            Self::Add([a_default, b_default])
+            | Self::Divide([a_default, b_default])
+            | Self::Modulo([a_default, b_default])
+            | Self::Multiply([a_default, b_default])
+            | Self::Power([a_default, b_default])
+            | Self::Subtract([a_default, b_default]) => {
+                let a = input_or(&mut inputs, "a", *a_default);
+                let b = input_or(&mut inputs, "b", *b_default);
+                let io = a.output.max(b.output);
+                let a_var = a.output.promote(io, &a.var);
+                let b_var = b.output.promote(io, &b.var);
+                let expression = match self {
+                    Self::Add(_) => format!("{} + {}", a_var, b_var),
+                    Self::Divide(_) => format!("{} / {}", a_var, b_var),
+                    Self::Modulo(_) => format!("{} % {}", a_var, b_var),
+                    Self::Multiply(_) => format!("{} * {}", a_var, b_var),
+                    Self::Power(_) => format!("pow({}, {})", a_var, b_var),
+                    _ => format!("{} - {}", a_var, b_var),
+                };
+                let mut builder = a;
+
+                builder.merge(&b);
+                builder.assign(node, &self.title().to_lowercase(), io, expression);
+
+                builder
+            }
+            Self::Bitangent => {
+                let mut builder = world_tangent(node);
+                let tangent = builder.var.clone();
+
+                builder.assign(
+                    node,
+                    "bitangent",
+                    ShaderIO::Vec3,
+                    format!("cross(world_normal, {t}.xyz) * {t}.w", t = tangent),
+                );
+
+                builder
+            }
+            Self::Color([r, g, b, a]) => {
+                let output = match output_label(node, output, &["color", "rgb"]) {
+                    Ok(output) => output,
+                    Err(e) => return ShaderBuilder::from_error(e),
+                };
+
+                if output == "color" {
+                    ShaderBuilder {
+                        output: ShaderIO::Vec4,
+                        var: ShaderIO::Vec4.literal(&[*r, *g, *b, *a]),
+                        ..default()
+                    }
+                } else {
+                    ShaderBuilder {
+                        output: ShaderIO::Vec3,
+                        var: ShaderIO::Vec3.literal(&[*r, *g, *b]),
+                        ..default()
+                    }
+                }
+            }
             Self::Component => {
-                let output = output.unwrap();
-                let mut builder = inputs
-                    .remove("value")
-                    .unwrap_or(None)
-                    .unwrap_or(ShaderBuilder::default());
-                let input_var = builder.var;
-                let input_io = builder.output;
-
-                builder.output = ShaderIO::F32;
-                builder.var = format!("{}_{}", input_var, output);
-
-                builder.content.push(format!(
-                    "let {} = {}.{};",
-                    builder.var,
-                    input_io.transform(ShaderIO::Vec4, &input_var, Some(0.0)),
+                let output = match output_label(node, output, &["x", "y", "z", "w"]) {
+                    Ok(output) => output,
+                    Err(e) => return ShaderBuilder::from_error(e),
+                };
+                let mut builder = required_input(node, &mut inputs, "value");
+                let input_var = builder.var.clone();
+
+                builder.assign(
+                    node,
                     output,
-                ));
+                    ShaderIO::F32,
+                    format!(
+                        "{}.{}",
+                        builder
+                            .output
+                            .transform(ShaderIO::Vec4, &input_var, Some(0.0)),
+                        output,
+                    ),
+                );
+
+                builder
+            }
+            Self::Cross => {
+                let a = required_input(node, &mut inputs, "a");
+                let b = required_input(node, &mut inputs, "b");
+                let expression = format!(
+                    "cross({}, {})",
+                    a.output.transform(ShaderIO::Vec3, &a.var, None),
+                    b.output.transform(ShaderIO::Vec3, &b.var, None),
+                );
+                let mut builder = a;
+
+                builder.merge(&b);
+                builder.assign(node, "cross", ShaderIO::Vec3, expression);
+
+                builder
+            }
+            Self::Distance => {
+                let a = required_input(node, &mut inputs, "a");
+                let b = required_input(node, &mut inputs, "b");
+
+                call_returning(node, "distance", &[a, b], Some(ShaderIO::F32))
+            }
+            Self::Dot => {
+                let a = required_input(node, &mut inputs, "a");
+                let b = required_input(node, &mut inputs, "b");
+
+                if a.output.max(b.output) != ShaderIO::F32 {
+                    return call_returning(node, "dot", &[a, b], Some(ShaderIO::F32));
+                }
+
+                // `dot` only takes vectors, the dot product of two scalars is their product.
+                let expression = format!("{} * {}", a.var, b.var);
+                let mut builder = a;
+
+                builder.merge(&b);
+                builder.assign(node, "dot", ShaderIO::F32, expression);
 
                 builder
             }
             Self::Extend(input) => {
-                let mut builder = inputs
-                    .remove("value")
-                    .unwrap_or(None)
-                    .unwrap_or(ShaderBuilder::default());
-                let input_var = builder.var;
-                let input_io = builder.output;
-
-                builder.output = input_io.extend();
-                builder.var = format!("{}_{}", input_var, "extend");
-
-                builder.content.push(format!(
-                    "let {} = {};",
-                    builder.var,
-                    input_io.transform(builder.output, &input_var, Some(input.value))
-                ));
+                let mut builder = required_input(node, &mut inputs, "value");
+                // A Vec4 is truncated to fit the input, as its link is marked, and extended again.
+                let input_io = builder.output.min(ShaderIO::Vec3);
+                let input_var = builder.output.transform(input_io, &builder.var, None);
+
+                builder.assign(
+                    node,
+                    "extend",
+                    input_io.extend(),
+                    input_io.transform(input_io.extend(), &input_var, Some(input.value)),
+                );
 
                 builder
             }
-            Self::MaterialPreview => inputs
-                .remove("input")
-                .unwrap_or(None)
-                .unwrap_or(ShaderBuilder::default()),
-            Self::Normal => {
-                let mut content = Vec::new();
-                let out = output.unwrap();
+            Self::Float(value) => ShaderBuilder::constant(value.value),
+            Self::Fresnel(power) => {
+                let normal = inputs
+                    .remove("normal")
+                    .flatten()
+                    .unwrap_or_else(|| ShaderBuilder {
+                        output: ShaderIO::Vec3,
+                        var: "world_normal".to_string(),
+                        ..default()
+                    });
+                let view = inputs
+                    .remove("view")
+                    .flatten()
+                    .unwrap_or_else(|| view_direction(node));
+                let power = input_or(&mut inputs, "power", power.value);
+                let expression = format!(
+                    "fresnel({}, {}, {})",
+                    normal.output.promote(ShaderIO::Vec3, &normal.var),
+                    view.output.promote(ShaderIO::Vec3, &view.var),
+                    power.output.transform(ShaderIO::F32, &power.var, None),
+                );
+                let mut builder = normal;
 
-                let (io, var) = if out == "normal" {
-                    (ShaderIO::Vec3, "world_normal".to_string())
-                } else {
-                    content.push(format!("let world_normal_{} = world_normal.{};", out, out));
+                builder.merge(&view);
+                builder.merge(&power);
+                builder.add_global(FRESNEL);
+                builder.assign(node, "fresnel", ShaderIO::F32, expression);
+
+                builder
+            }
+            Self::Length => {
+                let value = required_input(node, &mut inputs, "value");
 
-                    (ShaderIO::F32, format!("world_normal_{}", out))
+                call_returning(node, "length", &[value], Some(ShaderIO::F32))
+            }
+            Self::MaterialPreview(settings) => {
+                let mut builder = match settings.lighting {
+                    Lighting::Unlit => color_input(node, &mut inputs, "color", default()),
+                    Lighting::Pbr => pbr_color(node, &mut inputs),
                 };
+                let offset = inputs.remove("position_offset").flatten();
+                let varying = inputs.remove("varying").flatten();
 
-                ShaderBuilder {
-                    content,
-                    output: io,
-                    var,
+                if offset.is_some() || varying.is_some() {
+                    builder.merge_vertex(offset.as_ref(), varying.as_ref());
                 }
+
+                builder.blend_mode = settings.blend_mode;
+                builder
             }
-            Self::Print => {
-                let builder = inputs
-                    .remove("output")
-                    .unwrap_or(None)
-                    .unwrap_or(ShaderBuilder::default());
-                let shader = builder.build().unwrap();
+            Self::Mix => {
+                let a = required_input(node, &mut inputs, "a");
+                let b = required_input(node, &mut inputs, "b");
+                let t = input_or(&mut inputs, "t", 0.5);
 
-                println!("{}", shader);
+                call(node, "mix", &[a, b, t])
+            }
+            Self::Normal => {
+                let out = match output_label(node, output, &["normal", "x", "y", "z"]) {
+                    Ok(out) => out,
+                    Err(e) => return ShaderBuilder::from_error(e),
+                };
+                let mut builder = ShaderBuilder {
+                    output: ShaderIO::Vec3,
+                    var: "world_normal".to_string(),
+                    ..default()
+                };
+
+                if out != "normal" {
+                    builder.assign(node, out, ShaderIO::F32, format!("world_normal.{}", out));
+                }
 
                 builder
             }
-            Self::Saturate => {
-                let mut builder = inputs
-                    .remove("value")
-                    .unwrap_or(None)
-                    .unwrap_or(ShaderBuilder::default());
-                let input_var = builder.var;
-
-                builder.var = format!("{}_{}", &input_var, "saturate");
-                builder.content.push(format!(
-                    "let {} = clamp({}, {}, {});",
-                    builder.var,
-                    input_var,
-                    builder.output.fill(0.0),
-                    builder.output.fill(1.0),
-                ));
+            Self::Normalize => {
+                let value = required_input(node, &mut inputs, "value");
+                // `normalize` only takes vectors, the direction of a scalar is its sign.
+                let function = match value.output {
+                    ShaderIO::F32 => "sign",
+                    _ => "normalize",
+                };
+
+                call(node, function, &[value])
+            }
+            Self::Parameter(parameter) => {
+                let mut builder = ShaderBuilder::default();
+
+                builder.add_parameter(node, parameter);
+                builder.assign(
+                    node,
+                    "parameter",
+                    parameter.io,
+                    ShaderIO::Vec4.transform(
+                        parameter.io,
+                        &format!("parameters.{}", parameter.field()),
+                        None,
+                    ),
+                );
 
                 builder
             }
-            Self::Texture => {
-                let mut var = "texture_color".to_string();
-                let mut content = vec![
-                    "let texture_color = textureSample(texture, texture_sampler, uv);".to_string(),
-                ];
-                let output = output.unwrap();
-                let mut io = ShaderIO::Vec4;
+            Self::PerlinNoise([scale, seed])
+            | Self::SimplexNoise([scale, seed])
+            | Self::ValueNoise([scale, seed])
+            | Self::Voronoi([scale, seed]) => {
+                let labels: &[&str] = match self {
+                    Self::Voronoi(_) => &["distance", "cell"],
+                    _ => &["noise"],
+                };
+                let output = match output_label(node, output, labels) {
+                    Ok(output) => output,
+                    Err(e) => return ShaderBuilder::from_error(e),
+                };
+                let (function, helper, io) = match self {
+                    Self::PerlinNoise(_) => ("perlin_noise", PERLIN_NOISE, ShaderIO::F32),
+                    Self::SimplexNoise(_) => ("simplex_noise", SIMPLEX_NOISE, ShaderIO::F32),
+                    Self::ValueNoise(_) => ("value_noise", VALUE_NOISE, ShaderIO::F32),
+                    _ => ("voronoi", VORONOI, ShaderIO::Vec2),
+                };
+                let uv = uv_input(&mut inputs);
+                let scale = input_or(&mut inputs, "scale", *scale);
+                let seed = input_or(&mut inputs, "seed", *seed);
+                let mut builder = ShaderBuilder::default();
 
-                if output != "color" {
-                    var = format!("{}_{}", var, output);
-                    content.push(format!("let {} = texture_color.{};", var, output));
-                    io = ShaderIO::F32;
-                }
+                builder.merge(&uv);
+                builder.merge(&scale);
+                builder.merge(&seed);
+                builder.add_global(NOISE_HASH);
+                builder.add_global(helper);
+                builder.assign(
+                    node,
+                    function,
+                    io,
+                    format!(
+                        "{}({} * {} + {} * vec2<f32>(12.9898, 78.233))",
+                        function,
+                        uv.output.transform(ShaderIO::Vec2, &uv.var, None),
+                        scale.output.transform(ShaderIO::F32, &scale.var, None),
+                        seed.output.transform(ShaderIO::F32, &seed.var, None),
+                    ),
+                );
 
-                ShaderBuilder {
-                    content,
-                    output: io,
-                    var,
+                if output != "noise" {
+                    let component = if output == "distance" { "x" } else { "y" };
+
+                    builder.assign(
+                        node,
+                        output,
+                        ShaderIO::F32,
+                        format!("{}.{}", builder.var, component),
+                    );
                 }
+
+                builder
             }
-            Self::UV => {
-                let mut content = Vec::new();
-                let out = output.unwrap();
+            Self::Position => {
+                let output = match output_label(node, output, &["world", "object"]) {
+                    Ok(output) => output,
+                    Err(e) => return ShaderBuilder::from_error(e),
+                };
+                let mut builder = ShaderBuilder::default();
 
-                let (io, var) = if out == "uv" {
-                    (ShaderIO::Vec2, "uv".to_string())
+                if output == "object" {
+                    // The transposed inverse transpose of the model transform is its inverse.
+                    builder.add_global(MESH_BINDINGS);
+                    builder.assign(
+                        node,
+                        "object_position",
+                        ShaderIO::Vec3,
+                        "(transpose(mesh.inverse_transpose_model) * world_position).xyz"
+                            .to_string(),
+                    );
                 } else {
-                    content.push(format!("let uv_{} = uv.{};", out, out));
+                    builder.assign(
+                        node,
+                        "world_position",
+                        ShaderIO::Vec3,
+                        "world_position.xyz".to_string(),
+                    );
+                }
+
+                builder
+            }
+            // Printed by the editor once the graph is resolved.
+            Self::Print => optional_input(&mut inputs, "output"),
+            Self::Reflect => {
+                let incident = required_input(node, &mut inputs, "incident");
+                let normal = required_input(node, &mut inputs, "normal");
+
+                if incident.output.max(normal.output) != ShaderIO::F32 {
+                    return call(node, "reflect", &[incident, normal]);
+                }
+
+                // `reflect` only takes vectors, so spell it out for scalars.
+                let expression = format!(
+                    "{i} - 2.0 * {n} * {i} * {n}",
+                    i = incident.var,
+                    n = normal.var
+                );
+                let mut builder = incident;
 
-                    (ShaderIO::F32, format!("uv_{}", out))
+                builder.merge(&normal);
+                builder.assign(node, "reflect", ShaderIO::F32, expression);
+
+                builder
+            }
+            Self::Saturate => {
+                let mut builder = required_input(node, &mut inputs, "value");
+                let input_var = builder.var.clone();
+
+                builder.assign(
+                    node,
+                    "saturate",
+                    builder.output,
+                    format!(
+                        "clamp({}, {}, {})",
+                        input_var,
+                        builder.output.fill(0.0),
+                        builder.output.fill(1.0),
+                    ),
+                );
+
+                builder
+            }
+            Self::Smoothstep => {
+                let edge0 = input_or(&mut inputs, "edge0", 0.0);
+                let edge1 = input_or(&mut inputs, "edge1", 1.0);
+                let x = required_input(node, &mut inputs, "x");
+
+                call(node, "smoothstep", &[edge0, edge1, x])
+            }
+            Self::Step => {
+                let edge = input_or(&mut inputs, "edge", 0.5);
+                let x = required_input(node, &mut inputs, "x");
+
+                call(node, "step", &[edge, x])
+            }
+            Self::Tangent => {
+                let mut builder = world_tangent(node);
+                let tangent = builder.var.clone();
+
+                builder.assign(node, "tangent", ShaderIO::Vec3, format!("{}.xyz", tangent));
+
+                builder
+            }
+            Self::Texture(settings) => {
+                let output = match output_label(node, output, &["color", "r", "g", "b"]) {
+                    Ok(output) => output,
+                    Err(e) => return ShaderBuilder::from_error(e),
+                };
+                let uv = uv_input(&mut inputs);
+                let lod = inputs.remove("lod").flatten();
+                let bias = inputs.remove("bias").flatten();
+
+                // Sampling at an explicit LOD leaves no automatic one to bias.
+                if lod.is_some() && bias.is_some() {
+                    return ShaderBuilder::from_error(ShaderError::ExclusiveInputs {
+                        node,
+                        a: "lod",
+                        b: "bias",
+                    });
+                }
+
+                let mut builder = ShaderBuilder {
+                    // Only an explicit LOD needs no derivatives, so only it works per vertex.
+                    fragment_only: lod.is_none().then_some(node),
+                    ..default()
                 };
+                let texture = builder.add_texture(node, settings);
+                let uv_var = uv.output.transform(ShaderIO::Vec2, &uv.var, None);
+                let expression = match (&lod, &bias) {
+                    (Some(lod), _) => format!(
+                        "textureSampleLevel({t}, {t}_sampler, {}, {})",
+                        uv_var,
+                        lod.output.transform(ShaderIO::F32, &lod.var, None),
+                        t = texture
+                    ),
+                    (_, Some(bias)) => format!(
+                        "textureSampleBias({t}, {t}_sampler, {}, {})",
+                        uv_var,
+                        bias.output.transform(ShaderIO::F32, &bias.var, None),
+                        t = texture
+                    ),
+                    (None, None) => {
+                        format!("textureSample({t}, {t}_sampler, {})", uv_var, t = texture)
+                    }
+                };
+
+                builder.merge(&uv);
 
-                ShaderBuilder {
-                    content,
-                    output: io,
-                    var,
+                for input in lod.iter().chain(&bias) {
+                    builder.merge(input);
                 }
+
+                builder.assign(node, "texture_color", ShaderIO::Vec4, expression);
+
+                if output != "color" {
+                    builder.assign(
+                        node,
+                        output,
+                        ShaderIO::F32,
+                        format!("{}.{}", builder.var, output),
+                    );
+                }
+
+                builder
             }
-            Self::Vector => {
-                static mut COUNTER: u32 = 0;
+            Self::Time => {
+                let output = match output_label(node, output, &["time", "sin", "cos", "delta"]) {
+                    Ok(output) => output,
+                    Err(e) => return ShaderBuilder::from_error(e),
+                };
+                let expression = match output {
+                    "time" => "globals.time".to_string(),
+                    "delta" => "globals.delta_time".to_string(),
+                    function => format!("{}(globals.time)", function),
+                };
+                let mut builder = ShaderBuilder::default();
 
+                builder.add_global(VIEW_BINDINGS);
+                builder.assign(node, output, ShaderIO::F32, expression);
+
+                builder
+            }
+            Self::UV => {
+                let out = match output_label(node, output, &["uv", "x", "y"]) {
+                    Ok(out) => out,
+                    Err(e) => return ShaderBuilder::from_error(e),
+                };
                 let mut builder = ShaderBuilder {
-                    output: ShaderIO::Vec4,
-                    var: format!("vec_{}", unsafe { COUNTER }),
+                    output: ShaderIO::Vec2,
+                    var: "uv".to_string(),
                     ..default()
                 };
 
-                unsafe {
-                    COUNTER += 1;
+                if out != "uv" {
+                    builder.assign(node, out, ShaderIO::F32, format!("uv.{}", out));
                 }
 
+                builder
+            }
+            Self::Varying => {
+                let mut builder = ShaderBuilder {
+                    output: ShaderIO::Vec4,
+                    var: "custom_varying".to_string(),
+                    fragment_only: Some(node),
+                    ..default()
+                };
+
+                builder.merge_vertex(None, None);
+                builder
+            }
+            Self::Vec2(values) => ShaderBuilder {
+                output: ShaderIO::Vec2,
+                var: ShaderIO::Vec2.literal(values),
+                ..default()
+            },
+            Self::Vec3(values) => ShaderBuilder {
+                output: ShaderIO::Vec3,
+                var: ShaderIO::Vec3.literal(values),
+                ..default()
+            },
+            Self::Vec4(values) => ShaderBuilder {
+                output: ShaderIO::Vec4,
+                var: ShaderIO::Vec4.literal(values),
+                ..default()
+            },
+            Self::Vector => {
+                let mut builder = ShaderBuilder::default();
                 let mut components = Vec::new();
 
                 for input in ["x", "y", "z", "w"].iter() {
-                    let mut value = inputs
-                        .remove(*input)
-                        .unwrap_or(None)
-                        .unwrap_or(ShaderBuilder::default());
+                    let value = optional_input(&mut inputs, input);
 
-                    builder.content.append(&mut value.content);
+                    builder.merge(&value);
                     components.push(
                         value
                             .output
@@ -195,25 +1015,82 @@ impl FlowNodeSet for ShaderNodes {
                     );
                 }
 
-                builder.content.push(format!(
-                    "let {} = vec4({});",
-                    builder.var,
-                    components.join(", ")
-                ));
+                builder.assign(
+                    node,
+                    "vec",
+                    ShaderIO::Vec4,
+                    format!("vec4({})", components.join(", ")),
+                );
+
+                builder
+            }
+            Self::VertexColor => {
+                let mut builder = ShaderBuilder::default();
+
+                builder.assign_if_def(
+                    node,
+                    "vertex_color",
+                    ShaderIO::Vec4,
+                    "VERTEX_COLORS",
+                    "color".to_string(),
+                    ShaderIO::Vec4.fill(1.0),
+                );
 
                 builder
             }
+            Self::ViewDirection => view_direction(node),
         }
     }
+}
+
+impl FlowNodeSet for ShaderNodes {
+    type NodeIO = ShaderBuilder;
+
+    /// Resolves the node without knowing its entity, so the variables of different nodes may
+    /// clash.
+    ///
+    /// The editor only uses this to learn when the graph changed and resolves it again through
+    /// [`ShaderGraph::resolve_entity`](crate::graph::ShaderGraph::resolve_entity).
+    fn resolve(
+        &self,
+        inputs: HashMap<String, Option<Self::NodeIO>>,
+        output: Option<&str>,
+    ) -> Self::NodeIO {
+        self.resolve_node(Entity::PLACEHOLDER, inputs, output)
+    }
 
     fn template(self) -> FlowNodeTemplate<Self> {
+        // A swatch above a slider for each RGBA and HSV channel.
+        let color_size = 132.0;
         let preview_size = 400.0;
+        // Two rows of output settings under the preview.
+        let preview_controls = 48.0;
         let texture_size = 200.0;
 
         let mut template = match self {
+            Self::Add(_)
+            | Self::Divide(_)
+            | Self::Modulo(_)
+            | Self::Multiply(_)
+            | Self::Power(_)
+            | Self::Subtract(_) => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("result")]),
+                slot: Some(FlowNodeSlot::new(20.0)),
+                ..default()
+            },
+            Self::Bitangent => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("bitangent")]),
+                ..default()
+            },
+            Self::Color(_) => FlowNodeTemplate {
+                outputs: Some(vec![
+                    FlowNodeOutput::from_label("color"),
+                    FlowNodeOutput::from_label("rgb"),
+                ]),
+                slot: Some(FlowNodeSlot::new(color_size)),
+                ..default()
+            },
             Self::Component => FlowNodeTemplate {
-                title: "Component".to_string(),
-                inputs: Some(vec![FlowNodeInput::from_label("value")]),
                 outputs: Some(vec![
                     FlowNodeOutput::from_label("x"),
                     FlowNodeOutput::from_label("y"),
@@ -222,22 +1099,39 @@ impl FlowNodeSet for ShaderNodes {
                 ]),
                 ..default()
             },
+            Self::Cross | Self::Distance | Self::Dot => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("result")]),
+                ..default()
+            },
             Self::Extend(_) => FlowNodeTemplate {
-                title: "Extend".to_string(),
-                inputs: Some(vec![FlowNodeInput::from_label("value")]),
                 outputs: Some(vec![FlowNodeOutput::from_label("vec")]),
                 slot: Some(FlowNodeSlot::new(20.0)),
                 ..default()
             },
-            Self::MaterialPreview => FlowNodeTemplate {
-                title: "Preview".to_string(),
-                inputs: Some(vec![FlowNodeInput::from_label("input")]),
+            Self::Fresnel(_) => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("fresnel")]),
+                slot: Some(FlowNodeSlot::new(20.0)),
+                ..default()
+            },
+            Self::Length | Self::Normalize => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("result")]),
+                ..default()
+            },
+            Self::Float(_) | Self::Vec2(_) | Self::Vec3(_) | Self::Vec4(_) => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("value")]),
+                slot: Some(FlowNodeSlot::new(20.0)),
+                ..default()
+            },
+            Self::MaterialPreview(_) => FlowNodeTemplate {
                 width: preview_size,
-                slot: Some(FlowNodeSlot::new(preview_size)),
+                slot: Some(FlowNodeSlot::new(preview_size + preview_controls)),
+                ..default()
+            },
+            Self::Mix => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("result")]),
                 ..default()
             },
             Self::Normal => FlowNodeTemplate {
-                title: "Normal".to_string(),
                 outputs: Some(vec![
                     FlowNodeOutput::from_label("normal"),
                     FlowNodeOutput::from_label("x"),
@@ -246,19 +1140,49 @@ impl FlowNodeSet for ShaderNodes {
                 ]),
                 ..default()
             },
-            Self::Print => FlowNodeTemplate {
-                title: "Print".to_string(),
-                inputs: Some(vec![FlowNodeInput::from_label("output")]),
+            // The name above a row of component inputs.
+            Self::Parameter(_) => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("value")]),
+                slot: Some(FlowNodeSlot::new(44.0)),
+                width: 200.0,
+                ..default()
+            },
+            Self::PerlinNoise(_) | Self::SimplexNoise(_) | Self::ValueNoise(_) => {
+                FlowNodeTemplate {
+                    outputs: Some(vec![FlowNodeOutput::from_label("noise")]),
+                    slot: Some(FlowNodeSlot::new(20.0)),
+                    ..default()
+                }
+            }
+            Self::Position => FlowNodeTemplate {
+                outputs: Some(vec![
+                    FlowNodeOutput::from_label("world"),
+                    FlowNodeOutput::from_label("object"),
+                ]),
+                ..default()
+            },
+            Self::Print => FlowNodeTemplate { ..default() },
+            Self::Reflect => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("result")]),
                 ..default()
             },
             Self::Saturate => FlowNodeTemplate {
-                title: "Saturate".to_string(),
-                inputs: Some(vec![FlowNodeInput::from_label("value")]),
                 outputs: Some(vec![FlowNodeOutput::from_label("saturated")]),
                 ..default()
             },
-            Self::Texture => FlowNodeTemplate {
-                title: "Texture".to_string(),
+            Self::Smoothstep => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("result")]),
+                ..default()
+            },
+            Self::Step => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("result")]),
+                ..default()
+            },
+            Self::Tangent => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("tangent")]),
+                ..default()
+            },
+            Self::Texture(_) => FlowNodeTemplate {
                 outputs: Some(vec![
                     FlowNodeOutput::from_label("color"),
                     FlowNodeOutput::from_label("r"),
@@ -269,8 +1193,16 @@ impl FlowNodeSet for ShaderNodes {
                 width: texture_size,
                 ..default()
             },
+            Self::Time => FlowNodeTemplate {
+                outputs: Some(vec![
+                    FlowNodeOutput::from_label("time"),
+                    FlowNodeOutput::from_label("sin"),
+                    FlowNodeOutput::from_label("cos"),
+                    FlowNodeOutput::from_label("delta"),
+                ]),
+                ..default()
+            },
             Self::UV => FlowNodeTemplate {
-                title: "UV".to_string(),
                 outputs: Some(vec![
                     FlowNodeOutput::from_label("uv"),
                     FlowNodeOutput::from_label("x"),
@@ -278,21 +1210,64 @@ impl FlowNodeSet for ShaderNodes {
                 ]),
                 ..default()
             },
+            Self::Varying => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("value")]),
+                ..default()
+            },
             Self::Vector => FlowNodeTemplate {
-                title: "Vector".to_string(),
-                inputs: Some(vec![
-                    FlowNodeInput::from_label("x"),
-                    FlowNodeInput::from_label("y"),
-                    FlowNodeInput::from_label("z"),
-                    FlowNodeInput::from_label("w"),
-                ]),
                 outputs: Some(vec![FlowNodeOutput::from_label("vec")]),
                 ..default()
             },
+            Self::VertexColor => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("color")]),
+                ..default()
+            },
+            Self::ViewDirection => FlowNodeTemplate {
+                outputs: Some(vec![FlowNodeOutput::from_label("direction")]),
+                ..default()
+            },
+            Self::Voronoi(_) => FlowNodeTemplate {
+                outputs: Some(vec![
+                    FlowNodeOutput::from_label("distance"),
+                    FlowNodeOutput::from_label("cell"),
+                ]),
+                slot: Some(FlowNodeSlot::new(20.0)),
+                ..default()
+            },
         };
 
+        let inputs = self.inputs();
+
+        if !inputs.is_empty() {
+            template.inputs = Some(
+                inputs
+                    .iter()
+                    .map(|(label, _)| FlowNodeInput::from_label(label))
+                    .collect(),
+            );
+        }
+
+        template.title = self.title().to_string();
         template.node = self;
 
         template
     }
 }
+
+/// Stores a [`NumberInput`] as its plain value in graph files.
+mod number_input {
+    use bevy_flow_node::widgets::NumberInput;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(input: &NumberInput, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f32(input.value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<NumberInput, D::Error> {
+        let value = f32::deserialize(deserializer)?;
+
+        Ok(NumberInput { value })
+    }
+}
@@ -0,0 +1,87 @@
+pub const NOISE_HASH: &str = r#"fn noise_hash2(p: vec2<f32>) -> f32 {
+    let q = fract(p * vec2<f32>(123.34, 456.21));
+    let r = q + dot(q, q + 45.32);
+    return fract(r.x * r.y);
+}
+
+fn noise_hash22(p: vec2<f32>) -> vec2<f32> {
+    let q = vec2<f32>(dot(p, vec2<f32>(127.1, 311.7)), dot(p, vec2<f32>(269.5, 183.3)));
+    return fract(sin(q) * 43758.5453);
+}"#;
+
+pub const VALUE_NOISE: &str = r#"fn value_noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    let a = noise_hash2(i);
+    let b = noise_hash2(i + vec2<f32>(1.0, 0.0));
+    let c = noise_hash2(i + vec2<f32>(0.0, 1.0));
+    let d = noise_hash2(i + vec2<f32>(1.0, 1.0));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y);
+}"#;
+
+pub const PERLIN_NOISE: &str = r#"fn perlin_noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * f * (f * (f * 6.0 - 15.0) + 10.0);
+    let a = dot(noise_hash22(i) * 2.0 - 1.0, f);
+    let b = dot(noise_hash22(i + vec2<f32>(1.0, 0.0)) * 2.0 - 1.0, f - vec2<f32>(1.0, 0.0));
+    let c = dot(noise_hash22(i + vec2<f32>(0.0, 1.0)) * 2.0 - 1.0, f - vec2<f32>(0.0, 1.0));
+    let d = dot(noise_hash22(i + vec2<f32>(1.0, 1.0)) * 2.0 - 1.0, f - vec2<f32>(1.0, 1.0));
+    return mix(mix(a, b, u.x), mix(c, d, u.x), u.y) * 0.5 + 0.5;
+}"#;
+
+pub const SIMPLEX_NOISE: &str = r#"fn simplex_mod289(x: vec3<f32>) -> vec3<f32> {
+    return x - floor(x * (1.0 / 289.0)) * 289.0;
+}
+
+fn simplex_permute(x: vec3<f32>) -> vec3<f32> {
+    return simplex_mod289((x * 34.0 + 1.0) * x);
+}
+
+fn simplex_noise(v: vec2<f32>) -> f32 {
+    let c = vec4<f32>(0.211324865405187, 0.366025403784439, -0.577350269189626, 0.024390243902439);
+    var i = floor(v + dot(v, c.yy));
+    let x0 = v - i + dot(i, c.xx);
+    var i1 = vec2<f32>(0.0, 1.0);
+    if (x0.x > x0.y) {
+        i1 = vec2<f32>(1.0, 0.0);
+    }
+    var x12 = x0.xyxy + c.xxzz;
+    x12 = vec4<f32>(x12.xy - i1, x12.zw);
+    i = i - floor(i * (1.0 / 289.0)) * 289.0;
+    let p = simplex_permute(
+        simplex_permute(i.y + vec3<f32>(0.0, i1.y, 1.0)) + i.x + vec3<f32>(0.0, i1.x, 1.0)
+    );
+    var m = max(
+        0.5 - vec3<f32>(dot(x0, x0), dot(x12.xy, x12.xy), dot(x12.zw, x12.zw)),
+        vec3<f32>(0.0)
+    );
+    m = m * m;
+    m = m * m;
+    let x = 2.0 * fract(p * c.www) - 1.0;
+    let h = abs(x) - 0.5;
+    let a0 = x - floor(x + 0.5);
+    m = m * (1.79284291400159 - 0.85373472095314 * (a0 * a0 + h * h));
+    let g = vec3<f32>(a0.x * x0.x + h.x * x0.y, a0.yz * x12.xz + h.yz * x12.yw);
+    return 130.0 * dot(m, g) * 0.5 + 0.5;
+}"#;
+
+/// Returns the distance to the nearest cell point in `x` and a random ID for its cell in `y`.
+pub const VORONOI: &str = r#"fn voronoi(p: vec2<f32>) -> vec2<f32> {
+    let i = floor(p);
+    let f = fract(p);
+    var nearest = 8.0;
+    var cell = 0.0;
+    for (var y = -1; y <= 1; y = y + 1) {
+        for (var x = -1; x <= 1; x = x + 1) {
+            let offset = vec2<f32>(f32(x), f32(y));
+            let d = length(offset + noise_hash22(i + offset) - f);
+            if (d < nearest) {
+                nearest = d;
+                cell = noise_hash2(i + offset);
+            }
+        }
+    }
+    return vec2<f32>(nearest, cell);
+}"#;
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/// How the final color is combined with what is already behind it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub enum BlendMode {
+    /// Alpha is ignored.
+    #[default]
+    Opaque,
+    /// The color is blended over the background using its alpha.
+    Blend,
+    /// Fragments with an alpha below the cutoff are discarded, the rest are opaque.
+    Mask(f32),
+}
+
+/// How the final color is lit before it is written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Lighting {
+    /// The color is written as is.
+    #[default]
+    Unlit,
+    /// The inputs describe a surface lit by the scene's lights, like a `StandardMaterial`.
+    Pbr,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct OutputSettings {
+    #[serde(default)]
+    pub blend_mode: BlendMode,
+    #[serde(default)]
+    pub lighting: Lighting,
+}
@@ -0,0 +1,50 @@
+use naga::keywords::wgsl::RESERVED;
+use serde::{Deserialize, Serialize};
+
+use crate::shader::ShaderIO;
+
+/// Number of parameters the preview material has room for.
+pub const MAX_PARAMETERS: usize = 16;
+
+/// A named uniform that can be changed at runtime without rebuilding the shader.
+///
+/// Every parameter takes up a `vec4<f32>` in the generated `Parameters` struct, whatever its
+/// type, so the struct matches an array of [`MAX_PARAMETERS`] `Vec4`s on the CPU side. Game code
+/// sets parameters by name, so every Parameter node in a graph needs a name of its own.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ShaderParameter {
+    pub name: String,
+    pub io: ShaderIO,
+    pub value: [f32; 4],
+}
+
+impl ShaderParameter {
+    pub fn new(name: &str, io: ShaderIO, value: [f32; 4]) -> Self {
+        Self {
+            name: name.to_string(),
+            io,
+            value,
+        }
+    }
+
+    /// The parameter's name as a WGSL field name.
+    ///
+    /// Names WGSL reserves, keywords such as `vec2` and anything starting with `_` or a digit,
+    /// get a `p_` prefix.
+    pub fn field(&self) -> String {
+        let field = self
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+
+        if field.is_empty()
+            || field.starts_with(|c: char| !c.is_ascii_alphabetic())
+            || RESERVED.contains(&field.as_str())
+        {
+            format!("p_{}", field)
+        } else {
+            field
+        }
+    }
+}
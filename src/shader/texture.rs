@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+/// Number of textures the preview material has room for.
+///
+/// Bevy builds a material's bind group layout once per material type, before any material
+/// exists, so the number of texture bindings has to be fixed up front. Graphs with more Texture
+/// nodes than this fail with [`ShaderError::TooManyTextures`](crate::shader::ShaderError)
+/// instead of sharing a binding.
+///
+/// The texture of the `n`th Texture node is bound at `@binding(1 + 2 * n)` and its sampler right
+/// after it.
+pub const MAX_TEXTURES: usize = 4;
+
+/// What a texture returns for UVs outside `0.0..1.0`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum AddressMode {
+    /// The edge texels are stretched outwards.
+    #[default]
+    Clamp,
+    /// The texture tiles.
+    Repeat,
+    /// The texture tiles, flipping every other copy.
+    Mirror,
+}
+
+impl AddressMode {
+    /// The mode after this one, for cycling through them in the editor.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Clamp => Self::Repeat,
+            Self::Repeat => Self::Mirror,
+            Self::Mirror => Self::Clamp,
+        }
+    }
+}
+
+/// How texels are blended when the texture is magnified or minified.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum FilterMode {
+    /// The closest texel is used, keeping pixel art sharp.
+    Nearest,
+    /// Neighbouring texels are interpolated.
+    #[default]
+    Linear,
+}
+
+impl FilterMode {
+    /// The mode after this one, for cycling through them in the editor.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Nearest => Self::Linear,
+            Self::Linear => Self::Nearest,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub struct TextureSettings {
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub address_mode: AddressMode,
+    #[serde(default)]
+    pub mag_filter: FilterMode,
+    #[serde(default)]
+    pub min_filter: FilterMode,
+}
+
+impl TextureSettings {
+    /// Whether the image's own sampler, Bevy's clamping linear default, already fits.
+    pub fn default_sampler(&self) -> bool {
+        self.address_mode == AddressMode::default()
+            && self.mag_filter == FilterMode::default()
+            && self.min_filter == FilterMode::default()
+    }
+}
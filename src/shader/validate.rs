@@ -0,0 +1,338 @@
+use naga::{
+    front::wgsl,
+    valid::{Capabilities, ValidationFlags, Validator},
+};
+
+use crate::shader::{ShaderBuilder, ShaderError};
+
+/// Stand-ins for every Bevy import generated shaders use, so naga can check them without Bevy's
+/// preprocessor.
+///
+/// Each only declares what generated code refers to directly, with the same signatures as
+/// `bevy_pbr` 0.10. Modules only [`PBR_LIGHTING`](super::lighting::PBR_LIGHTING) relies on through
+/// `pbr` have empty stubs.
+const IMPORT_STUBS: &[(&str, &str)] = &[
+    (
+        "bevy_pbr::mesh_vertex_output",
+        r#"@location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,"#,
+    ),
+    (
+        "bevy_pbr::mesh_bindings",
+        r#"struct Mesh {
+    model: mat4x4<f32>,
+    inverse_transpose_model: mat4x4<f32>,
+    flags: u32,
+};
+@group(2) @binding(0)
+var<uniform> mesh: Mesh;"#,
+    ),
+    (
+        "bevy_pbr::mesh_functions",
+        r#"fn mesh_position_local_to_world(model: mat4x4<f32>, position: vec4<f32>) -> vec4<f32> {
+    return model * position;
+}
+fn mesh_position_world_to_clip(world_position: vec4<f32>) -> vec4<f32> {
+    return world_position;
+}
+fn mesh_normal_local_to_world(normal: vec3<f32>) -> vec3<f32> {
+    return normal;
+}"#,
+    ),
+    (
+        "bevy_pbr::mesh_view_bindings",
+        r#"struct View {
+    view_proj: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    world_position: vec3<f32>,
+};
+@group(0) @binding(0)
+var<uniform> view: View;
+struct Fog {
+    mode: u32,
+};
+const FOG_MODE_OFF: u32 = 0u;
+@group(0) @binding(4)
+var<uniform> fog: Fog;
+struct Globals {
+    time: f32,
+    delta_time: f32,
+    frame_count: u32,
+};
+@group(0) @binding(9)
+var<uniform> globals: Globals;"#,
+    ),
+    (
+        "bevy_pbr::pbr_types",
+        r#"struct StandardMaterial {
+    base_color: vec4<f32>,
+    emissive: vec4<f32>,
+    perceptual_roughness: f32,
+    metallic: f32,
+    reflectance: f32,
+    flags: u32,
+    alpha_cutoff: f32,
+};
+const STANDARD_MATERIAL_FLAGS_ALPHA_MODE_BLEND: u32 = 1073741824u;"#,
+    ),
+    ("bevy_pbr::utils", ""),
+    ("bevy_pbr::clustered_forward", ""),
+    ("bevy_pbr::lighting", ""),
+    ("bevy_pbr::pbr_ambient", ""),
+    ("bevy_pbr::shadows", ""),
+    ("bevy_pbr::fog", ""),
+    (
+        "bevy_pbr::pbr_functions",
+        r#"struct PbrInput {
+    material: StandardMaterial,
+    occlusion: f32,
+    frag_coord: vec4<f32>,
+    world_position: vec4<f32>,
+    world_normal: vec3<f32>,
+    N: vec3<f32>,
+    V: vec3<f32>,
+    is_orthographic: bool,
+    flags: u32,
+};
+fn pbr_input_new() -> PbrInput {
+    var pbr_input: PbrInput;
+    return pbr_input;
+}
+fn calculate_view(world_position: vec4<f32>, is_orthographic: bool) -> vec3<f32> {
+    return normalize(view.world_position - world_position.xyz);
+}
+fn pbr(in: PbrInput) -> vec4<f32> {
+    return in.material.base_color;
+}
+fn apply_fog(
+    input_color: vec4<f32>,
+    fragment_world_position: vec3<f32>,
+    view_world_position: vec3<f32>,
+) -> vec4<f32> {
+    return input_color;
+}"#,
+    ),
+];
+
+impl ShaderBuilder {
+    /// Parses and validates `source`, which must have come from [`ShaderBuilder::build`].
+    ///
+    /// Shaders importing a module without a stub are rejected, as they cannot be checked. Shader
+    /// defs are all treated as unset, as for a mesh with only positions, normals and UVs.
+    pub fn validate(&self, source: &str) -> Result<(), ShaderError> {
+        let (expanded, lines) = expand_imports(source)?;
+        let error = |line: Option<usize>, message: String| {
+            let line = line
+                .and_then(|line| line.checked_sub(1))
+                .and_then(|index| lines.get(index))
+                .copied()
+                .unwrap_or(0);
+
+            ShaderError::Invalid {
+                node: line
+                    .checked_sub(1)
+                    .and_then(|index| source.lines().nth(index))
+                    .and_then(declared_var)
+                    .and_then(|var| self.sources.get(var))
+                    .copied(),
+                line,
+                message,
+            }
+        };
+
+        let module = wgsl::parse_str(&expanded).map_err(|e| {
+            error(
+                // Some errors, such as unknown identifiers, come with an empty span at the start.
+                e.location(&expanded)
+                    .filter(|location| location.length > 0)
+                    .map(|location| location.line_number as usize),
+                e.message().to_string(),
+            )
+        })?;
+
+        Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .map_err(|e| {
+                error(
+                    // Spans go from the enclosing function down to the offending expression.
+                    e.spans()
+                        .last()
+                        .map(|(span, _)| span.location(&expanded).line_number as usize),
+                    e.as_inner().to_string(),
+                )
+            })?;
+
+        Ok(())
+    }
+}
+
+/// Replaces each `#import` in `source` with its stub and keeps the branches of `#ifdef` blocks
+/// taken when no shader def is set.
+///
+/// Returns the expanded source along with the original line number of every expanded line.
+fn expand_imports(source: &str) -> Result<(String, Vec<usize>), ShaderError> {
+    let mut expanded = String::new();
+    let mut lines = Vec::new();
+    // Whether the lines of each enclosing `#ifdef`/`#ifndef` block are kept.
+    let mut branches: Vec<bool> = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let directive = line.trim();
+
+        if directive.starts_with("#ifdef") {
+            branches.push(false);
+            continue;
+        } else if directive.starts_with("#ifndef") {
+            branches.push(true);
+            continue;
+        } else if directive.starts_with("#else") {
+            if let Some(branch) = branches.last_mut() {
+                *branch = !*branch;
+            }
+            continue;
+        } else if directive.starts_with("#endif") {
+            branches.pop();
+            continue;
+        } else if branches.contains(&false) {
+            continue;
+        }
+
+        let text = match directive.strip_prefix("#import") {
+            Some(import) => {
+                let import = import.trim();
+
+                IMPORT_STUBS
+                    .iter()
+                    .find(|(name, _)| *name == import)
+                    .map(|(_, stub)| *stub)
+                    .ok_or_else(|| ShaderError::Invalid {
+                        node: None,
+                        line: index + 1,
+                        message: format!("cannot check unknown import `{}`", import),
+                    })?
+            }
+            None => line,
+        };
+
+        for text in text.lines() {
+            expanded.push_str(text);
+            expanded.push('\n');
+            lines.push(index + 1);
+        }
+    }
+
+    Ok((expanded, lines))
+}
+
+/// The name declared by a `let` statement.
+fn declared_var(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix("let ")?
+        .split(|c: char| c.is_whitespace() || c == '=' || c == ':')
+        .next()
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Entity;
+    use bevy_flow_node::widgets::NumberInput;
+    use std::collections::HashMap;
+
+    use crate::shader::{
+        BlendMode,
+        Lighting,
+        NodeRef,
+        OutputSettings,
+        ShaderBuilder,
+        ShaderError,
+        ShaderIO,
+        ShaderNodes,
+    };
+
+    /// Builds and validates an output node with `inputs` connected to the given node outputs.
+    fn check(
+        settings: OutputSettings,
+        inputs: &[(&str, ShaderNodes, &str)],
+    ) -> Result<(), ShaderError> {
+        let inputs = inputs
+            .iter()
+            .enumerate()
+            .map(|(index, (input, node, output))| {
+                let entity = Entity::from_raw(index as u32 + 1);
+
+                (
+                    input.to_string(),
+                    Some(node.resolve_node(entity, HashMap::new(), Some(output))),
+                )
+            })
+            .collect();
+        let builder =
+            ShaderNodes::MaterialPreview(settings).resolve_node(Entity::from_raw(0), inputs, None);
+
+        builder.validate(&builder.build()?)
+    }
+
+    #[test]
+    fn every_output_node_validates() {
+        for lighting in [Lighting::Unlit, Lighting::Pbr] {
+            for blend_mode in [BlendMode::Opaque, BlendMode::Blend, BlendMode::Mask(0.5)] {
+                let settings = OutputSettings {
+                    blend_mode,
+                    lighting,
+                };
+                let vertex = [
+                    ("position_offset", ShaderNodes::Normal, "normal"),
+                    ("varying", ShaderNodes::UV, "uv"),
+                ];
+
+                assert_eq!(check(settings.clone(), &[]), Ok(()));
+                assert_eq!(check(settings, &vertex), Ok(()));
+            }
+        }
+    }
+
+    #[test]
+    fn unconnected_fresnel_validates() {
+        let fresnel = ShaderNodes::Fresnel(NumberInput { value: 5.0 });
+
+        assert_eq!(
+            check(OutputSettings::default(), &[("color", fresnel, "fresnel")]),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn invalid_code_is_traced_to_its_node() {
+        let mut builder = ShaderBuilder::default();
+
+        builder.assign(
+            NodeRef {
+                entity: Entity::from_raw(0),
+                title: "Broken",
+            },
+            "broken",
+            ShaderIO::F32,
+            "undeclared + 1.0".to_string(),
+        );
+
+        match builder.validate(&builder.build().unwrap()) {
+            Err(ShaderError::Invalid { node, .. }) => {
+                assert_eq!(node.map(|node| node.title), Some("Broken"))
+            }
+            result => panic!("expected a validation error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn unknown_imports_fail() {
+        let mut builder = ShaderBuilder::default();
+
+        builder.add_global("#import bevy_pbr::environment_map");
+
+        assert!(matches!(
+            builder.validate(&builder.build().unwrap()),
+            Err(ShaderError::Invalid { node: None, .. })
+        ));
+    }
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+use bevy_flow_node::assets::DefaultAssets;
+
+use crate::{export::ExportAction, graph::FileAction};
+
+const BUTTON_COLOR: Color = Color::rgb(0.2, 0.2, 0.2);
+const HOVERED_COLOR: Color = Color::rgb(0.3, 0.3, 0.3);
+
+#[derive(Default)]
+pub struct ToolbarPlugin;
+
+impl Plugin for ToolbarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(setup_toolbar)
+            .add_system(click_toolbar_button);
+    }
+}
+
+/// What a toolbar button does when clicked.
+#[derive(Component, Clone, Copy)]
+enum ToolbarButton {
+    File(FileAction),
+    Export(ExportAction),
+}
+
+const BUTTONS: [(&str, ToolbarButton); 7] = [
+    ("Open", ToolbarButton::File(FileAction::Open)),
+    ("Save", ToolbarButton::File(FileAction::Save)),
+    ("Save As", ToolbarButton::File(FileAction::SaveAs)),
+    ("Export", ToolbarButton::Export(ExportAction::Export)),
+    ("Export As", ToolbarButton::Export(ExportAction::ExportAs)),
+    (
+        "Live Export",
+        ToolbarButton::Export(ExportAction::ToggleLive),
+    ),
+    ("Header", ToolbarButton::Export(ExportAction::ToggleHeader)),
+];
+
+/// A row of buttons along the top of the window for actions that also have shortcuts.
+fn setup_toolbar(mut commands: Commands, assets: Res<DefaultAssets>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    left: Val::Px(8.0),
+                    top: Val::Px(8.0),
+                    ..default()
+                },
+                gap: Size::all(Val::Px(4.0)),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|toolbar| {
+            for (label, button) in BUTTONS {
+                toolbar
+                    .spawn((
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::new(
+                                    Val::Px(8.0),
+                                    Val::Px(8.0),
+                                    Val::Px(4.0),
+                                    Val::Px(4.0),
+                                ),
+                                ..default()
+                            },
+                            background_color: BUTTON_COLOR.into(),
+                            ..default()
+                        },
+                        button,
+                    ))
+                    .with_children(|button| {
+                        button.spawn(TextBundle::from_section(
+                            label,
+                            TextStyle {
+                                font: assets.font.clone(),
+                                font_size: 14.0,
+                                color: Color::WHITE,
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn click_toolbar_button(
+    mut buttons: Query<(&ToolbarButton, &Interaction, &mut BackgroundColor), Changed<Interaction>>,
+    mut ev_file: EventWriter<FileAction>,
+    mut ev_export: EventWriter<ExportAction>,
+) {
+    for (button, interaction, mut color) in buttons.iter_mut() {
+        *color = match interaction {
+            Interaction::None => BUTTON_COLOR,
+            Interaction::Hovered | Interaction::Clicked => HOVERED_COLOR,
+        }
+        .into();
+
+        if *interaction != Interaction::Clicked {
+            continue;
+        }
+
+        match button {
+            ToolbarButton::File(action) => ev_file.send(*action),
+            ToolbarButton::Export(action) => ev_export.send(*action),
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use bevy::{prelude::*, sprite::Anchor, window::PrimaryWindow};
+use bevy_flow_node::{
+    assets::DefaultAssets,
+    widget::{Widget, WidgetPlugin},
+    SlotWidget,
+};
+
+use crate::shader::ShaderNodes;
+
+use super::world_cursor;
+
+const SWATCH_HEIGHT: f32 = 30.0;
+const BAR_HEIGHT: f32 = 10.0;
+const BAR_GAP: f32 = 4.0;
+const PADDING: f32 = 8.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Channel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+    Hue,
+    Saturation,
+    Value,
+}
+
+const CHANNELS: [Channel; 7] = [
+    Channel::Red,
+    Channel::Green,
+    Channel::Blue,
+    Channel::Alpha,
+    Channel::Hue,
+    Channel::Saturation,
+    Channel::Value,
+];
+
+#[derive(Default)]
+pub struct ColorWidgetPlugin;
+
+impl Plugin for ColorWidgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(WidgetPlugin::<ShaderNodes, ColorWidget>::default())
+            .add_system(pick_color)
+            .add_system(update_color_widget);
+    }
+}
+
+/// A swatch with RGBA and HSV sliders, set by clicking or dragging along a slider.
+#[derive(Component, Clone, Default)]
+pub struct ColorWidget {
+    pub size: Vec2,
+    pub color: [f32; 4],
+    hsv: [f32; 3],
+    /// The channel whose slider the current drag started on.
+    dragging: Option<Channel>,
+    swatch: Option<Entity>,
+    fills: Vec<Entity>,
+    dirty: bool,
+}
+
+impl ColorWidget {
+    fn new(color: [f32; 4]) -> Self {
+        Self {
+            color,
+            hsv: rgb_to_hsv([color[0], color[1], color[2]]),
+            ..default()
+        }
+    }
+
+    fn bar_width(&self) -> f32 {
+        self.size.x - 2.0 * PADDING
+    }
+
+    /// Center of the bar for the channel at `index`, relative to the widget.
+    fn bar_center(&self, index: usize) -> Vec2 {
+        let top = self.size.y / 2.0 - SWATCH_HEIGHT - BAR_GAP;
+
+        Vec2::new(
+            0.0,
+            top - index as f32 * (BAR_HEIGHT + BAR_GAP) - BAR_HEIGHT / 2.0,
+        )
+    }
+
+    fn channel(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Red => self.color[0],
+            Channel::Green => self.color[1],
+            Channel::Blue => self.color[2],
+            Channel::Alpha => self.color[3],
+            Channel::Hue => self.hsv[0],
+            Channel::Saturation => self.hsv[1],
+            Channel::Value => self.hsv[2],
+        }
+    }
+
+    fn set_channel(&mut self, channel: Channel, value: f32) {
+        if self.channel(channel) == value {
+            return;
+        }
+
+        match channel {
+            Channel::Red | Channel::Green | Channel::Blue | Channel::Alpha => {
+                self.color[channel as usize] = value;
+                self.hsv = rgb_to_hsv([self.color[0], self.color[1], self.color[2]]);
+            }
+            Channel::Hue | Channel::Saturation | Channel::Value => {
+                self.hsv[channel as usize - Channel::Hue as usize] = value;
+
+                let [r, g, b] = hsv_to_rgb(self.hsv);
+
+                self.color = [r, g, b, self.color[3]];
+            }
+        }
+
+        self.dirty = true;
+    }
+
+    fn fill_color(&self, channel: Channel) -> Color {
+        match channel {
+            Channel::Red => Color::rgb(0.9, 0.2, 0.2),
+            Channel::Green => Color::rgb(0.2, 0.8, 0.2),
+            Channel::Blue => Color::rgb(0.2, 0.4, 0.9),
+            Channel::Alpha => Color::rgb(0.8, 0.8, 0.8),
+            Channel::Hue => {
+                let [r, g, b] = hsv_to_rgb([self.hsv[0], 1.0, 1.0]);
+
+                Color::rgb(r, g, b)
+            }
+            Channel::Saturation | Channel::Value => {
+                let [r, g, b] = hsv_to_rgb(self.hsv);
+
+                Color::rgb(r, g, b)
+            }
+        }
+    }
+}
+
+impl Widget for ColorWidget {
+    type WidgetValue = [f32; 4];
+
+    fn build(
+        &mut self,
+        entity: Entity,
+        commands: &mut Commands,
+        area: Vec2,
+        _assets: &Res<DefaultAssets>,
+    ) {
+        self.size = area;
+
+        let [r, g, b, a] = self.color;
+        let swatch = commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgba(r, g, b, a),
+                    custom_size: Some(Vec2::new(self.bar_width(), SWATCH_HEIGHT)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, (area.y - SWATCH_HEIGHT) / 2.0, 1.0),
+                ..default()
+            })
+            .id();
+        let mut children = vec![swatch];
+
+        self.swatch = Some(swatch);
+        self.fills.clear();
+
+        for (index, channel) in CHANNELS.iter().enumerate() {
+            let center = self.bar_center(index);
+            let background = commands
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(0.2, 0.2, 0.2),
+                        custom_size: Some(Vec2::new(self.bar_width(), BAR_HEIGHT)),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(center.extend(1.0)),
+                    ..default()
+                })
+                .id();
+            let fill = commands
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: self.fill_color(*channel),
+                        custom_size: Some(Vec2::new(
+                            self.bar_width() * self.channel(*channel),
+                            BAR_HEIGHT,
+                        )),
+                        anchor: Anchor::CenterLeft,
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(-self.bar_width() / 2.0, center.y, 2.0),
+                    ..default()
+                })
+                .id();
+
+            children.extend([background, fill]);
+            self.fills.push(fill);
+        }
+
+        commands.entity(entity).push_children(&children);
+    }
+
+    fn can_click(&self) -> bool {
+        true
+    }
+
+    fn clean(&mut self) {
+        self.dirty = false;
+    }
+
+    fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn get_value(&self) -> Self::WidgetValue {
+        self.color
+    }
+
+    fn size(&self) -> Vec2 {
+        self.size
+    }
+}
+
+impl SlotWidget<Self, ColorWidget> for ShaderNodes {
+    fn get_widget(&self) -> Option<ColorWidget> {
+        match self {
+            ShaderNodes::Color(color) => Some(ColorWidget::new(*color)),
+            _ => None,
+        }
+    }
+
+    fn set_value(&mut self, value: [f32; 4]) {
+        if let Self::Color(color) = self {
+            *color = value;
+        }
+    }
+}
+
+fn pick_color(
+    mouse: Res<Input<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut widgets: Query<(&mut ColorWidget, &GlobalTransform)>,
+) {
+    if mouse.just_released(MouseButton::Left) {
+        for (mut widget, _) in widgets.iter_mut() {
+            if widget.dragging.is_some() {
+                widget.dragging = None;
+            }
+        }
+    }
+
+    if !mouse.pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some(cursor) = world_cursor(&windows, &cameras) else {
+        return;
+    };
+
+    for (mut widget, transform) in widgets.iter_mut() {
+        let local = cursor - transform.translation().truncate();
+        let half_width = widget.bar_width() / 2.0;
+
+        // Drags only move the slider they started on, wherever the cursor goes afterwards.
+        if mouse.just_pressed(MouseButton::Left) {
+            let hit = (0..CHANNELS.len()).find(|index| {
+                local.x.abs() <= half_width
+                    && (local.y - widget.bar_center(*index).y).abs() <= BAR_HEIGHT / 2.0
+            });
+
+            widget.dragging = hit.map(|index| CHANNELS[index]);
+        }
+
+        if let Some(channel) = widget.dragging {
+            let value = (local.x + half_width) / widget.bar_width();
+
+            widget.set_channel(channel, value.clamp(0.0, 1.0));
+        }
+    }
+}
+
+fn update_color_widget(
+    widgets: Query<&ColorWidget, Changed<ColorWidget>>,
+    mut sprites: Query<&mut Sprite>,
+) {
+    for widget in widgets.iter() {
+        let [r, g, b, a] = widget.color;
+
+        if let Some(mut sprite) = widget
+            .swatch
+            .and_then(|swatch| sprites.get_mut(swatch).ok())
+        {
+            sprite.color = Color::rgba(r, g, b, a);
+        }
+
+        for (channel, fill) in CHANNELS.iter().zip(&widget.fills) {
+            if let Ok(mut sprite) = sprites.get_mut(*fill) {
+                sprite.color = widget.fill_color(*channel);
+                sprite.custom_size = Some(Vec2::new(
+                    widget.bar_width() * widget.channel(*channel),
+                    BAR_HEIGHT,
+                ));
+            }
+        }
+    }
+}
+
+fn rgb_to_hsv([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+    [hue / 6.0, saturation, max]
+}
+
+fn hsv_to_rgb([h, s, v]: [f32; 3]) -> [f32; 3] {
+    let sector = (h.fract() * 6.0).floor();
+    let f = h.fract() * 6.0 - sector;
+    let p = v * (1.0 - s);
+    let q = v * (1.0 - s * f);
+    let t = v * (1.0 - s * (1.0 - f));
+
+    match sector as u8 {
+        0 => [v, t, p],
+        1 => [q, v, p],
+        2 => [p, v, t],
+        3 => [p, q, v],
+        4 => [t, p, v],
+        _ => [v, p, q],
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use bevy_flow_node::{
+    assets::DefaultAssets,
+    widget::Widget,
+    widgets::{InputWidget, NumberInput},
+};
+
+pub const COMPONENT_GAP: f32 = 4.0;
+pub const COMPONENT_HEIGHT: f32 = 20.0;
+
+/// A widget holding a row of number inputs, one per vector component.
+pub trait ComponentsWidget: Component {
+    fn component(&self, index: usize) -> f32;
+
+    /// Stores the value typed into the input at `index`, marking the widget dirty.
+    fn set_component(&mut self, index: usize, value: f32);
+}
+
+/// Links a number input to the component it edits.
+#[derive(Component)]
+pub struct ComponentInput {
+    widget: Entity,
+    index: usize,
+}
+
+/// Spawns a number input for each of `values` as children of `widget`, spread across a row
+/// `width` wide centered on `center`.
+pub fn spawn_component_inputs(
+    widget: Entity,
+    values: &[f32],
+    center: Vec2,
+    width: f32,
+    commands: &mut Commands,
+    assets: &Res<DefaultAssets>,
+) {
+    let count = values.len() as f32;
+    let input_width = (width - COMPONENT_GAP * (count + 1.0)) / count;
+    let mut children = Vec::new();
+
+    for (index, value) in values.iter().enumerate() {
+        let x = center.x - width / 2.0
+            + COMPONENT_GAP
+            + input_width / 2.0
+            + index as f32 * (input_width + COMPONENT_GAP);
+        let mut input = InputWidget {
+            value: NumberInput { value: *value },
+            ..default()
+        };
+        let child = commands
+            .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+                x, center.y, 1.0,
+            )))
+            .id();
+
+        input.build(
+            child,
+            commands,
+            Vec2::new(input_width, COMPONENT_HEIGHT),
+            assets,
+        );
+        commands
+            .entity(child)
+            .insert((input, ComponentInput { widget, index }));
+        children.push(child);
+    }
+
+    commands.entity(widget).push_children(&children);
+}
+
+/// Copies edited number inputs into the widget of type `W` they belong to.
+pub fn read_component_inputs<W: ComponentsWidget>(
+    inputs: Query<(&ComponentInput, &InputWidget<NumberInput>), Changed<InputWidget<NumberInput>>>,
+    mut widgets: Query<&mut W>,
+) {
+    for (input, number) in inputs.iter() {
+        let Ok(mut widget) = widgets.get_mut(input.widget) else {
+            continue;
+        };
+        let value = number.get_value().value;
+
+        if widget.component(input.index) != value {
+            widget.set_component(input.index, value);
+        }
+    }
+}
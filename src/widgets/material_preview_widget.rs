@@ -5,28 +5,63 @@ use bevy::{
     render::{
         camera::RenderTarget,
         render_resource::{
+            self,
             AsBindGroup,
             Extent3d,
+            SamplerDescriptor,
             ShaderRef,
             TextureDescriptor,
             TextureDimension,
             TextureFormat,
             TextureUsages,
         },
+        texture::ImageSampler,
         view::RenderLayers,
     },
+    sprite::Anchor,
+    text::Text2dBounds,
+    utils::HashMap,
+    window::PrimaryWindow,
 };
 use bevy_flow_node::{
     assets::DefaultAssets,
     widget::{Widget, WidgetPlugin},
-    FlowNodeEvent,
     SlotWidget,
 };
 
-use crate::shader::ShaderNodes;
+use crate::{
+    graph::ShaderResolved,
+    shader::{
+        AddressMode,
+        BlendMode,
+        FilterMode,
+        Lighting,
+        OutputSettings,
+        ShaderNodes,
+        TextureSettings,
+        MAX_PARAMETERS,
+        MAX_TEXTURES,
+    },
+};
+
+use super::{
+    component_inputs::{
+        read_component_inputs,
+        spawn_component_inputs,
+        ComponentsWidget,
+        COMPONENT_GAP,
+        COMPONENT_HEIGHT,
+    },
+    world_cursor,
+};
 
 const PREVIEW_SHADER_HANDLE: HandleUntyped =
     HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 452747839445324907);
+const PREVIEW_VERTEX_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 452747839445324908);
+/// Passes Bevy's usual mesh varyings on while the graph has no vertex stage of its own.
+const PREVIEW_VERTEX_SHADER: &str = include_str!("preview_vertex.wgsl");
+const ERROR_COLOR: Color = Color::rgb(0.9, 0.3, 0.3);
 
 #[derive(Default)]
 pub struct MaterialPreviewWidgetPlugin;
@@ -39,35 +74,251 @@ impl Plugin for MaterialPreviewWidgetPlugin {
             "preview.wgsl",
             Shader::from_wgsl
         );
+        load_internal_asset!(
+            app,
+            PREVIEW_VERTEX_SHADER_HANDLE,
+            "preview_vertex.wgsl",
+            Shader::from_wgsl
+        );
         app.add_plugin(WidgetPlugin::<ShaderNodes, MaterialPreviewWidget>::default())
             .add_plugin(MaterialPlugin::<PreviewMaterial>::default())
+            .init_resource::<PreviewTextures>()
+            .add_system(bind_preview_textures)
+            .add_system(click_preview_option)
+            .add_system(read_component_inputs::<MaterialPreviewWidget>)
             .add_system(rotate_preview_mesh)
             .add_system(setup_material_preview)
-            .add_system(update_preview_material);
+            .add_system(update_preview_material)
+            .add_system(update_preview_options);
     }
 }
 
-#[derive(Component, Clone, Copy, Default)]
+/// A button under the preview, changed to the next setting when clicked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PreviewOption {
+    Lighting,
+    BlendMode,
+}
+
+const OPTIONS: [PreviewOption; 2] = [PreviewOption::Lighting, PreviewOption::BlendMode];
+
+/// Height of the controls under the preview: a row of option buttons above the mask cutoff.
+const CONTROLS_HEIGHT: f32 = 2.0 * (COMPONENT_HEIGHT + COMPONENT_GAP);
+
+/// The rendered preview of the graph above controls for the output settings.
+#[derive(Component, Clone, Default)]
 pub struct MaterialPreviewWidget {
     pub size: Vec2,
+    pub settings: OutputSettings,
+    /// The cutoff used when the blend mode is set to [`BlendMode::Mask`], kept while another mode
+    /// is selected.
+    cutoff: f32,
+    labels: Vec<Entity>,
+    dirty: bool,
 }
 
 #[derive(Component)]
 struct ReadyForPreview;
 
+/// Text over the preview describing why the graph could not be built.
+#[derive(Component)]
+struct PreviewError;
+
+/// Text over a node describing what is wrong with it.
+#[derive(Component)]
+struct NodeError;
+
+impl MaterialPreviewWidget {
+    fn new(settings: &OutputSettings) -> Self {
+        let cutoff = match settings.blend_mode {
+            BlendMode::Mask(cutoff) => cutoff,
+            _ => 0.5,
+        };
+
+        Self {
+            settings: settings.clone(),
+            cutoff,
+            ..default()
+        }
+    }
+
+    /// Center and size of the rendered preview, relative to the widget.
+    fn preview_rect(&self) -> (Vec2, Vec2) {
+        (
+            Vec2::new(0.0, CONTROLS_HEIGHT / 2.0),
+            Vec2::new(self.size.x, self.size.y - CONTROLS_HEIGHT),
+        )
+    }
+
+    /// Center of the row of controls at `row`, counted up from the bottom of the widget.
+    fn row_center(&self, row: usize) -> f32 {
+        -self.size.y / 2.0
+            + (COMPONENT_GAP + COMPONENT_HEIGHT) / 2.0
+            + row as f32 * (COMPONENT_HEIGHT + COMPONENT_GAP)
+    }
+
+    /// Center and size of the button for `option`, relative to the widget.
+    fn option_rect(&self, option: PreviewOption) -> (Vec2, Vec2) {
+        let half = self.size.x / 2.0;
+        let x = match option {
+            PreviewOption::Lighting => -half / 2.0,
+            PreviewOption::BlendMode => half / 2.0,
+        };
+
+        (
+            Vec2::new(x, self.row_center(1)),
+            Vec2::new(half, COMPONENT_HEIGHT),
+        )
+    }
+
+    fn option_text(&self, option: PreviewOption) -> String {
+        match (option, self.settings.blend_mode) {
+            (PreviewOption::Lighting, _) => format!("Lighting: {:?}", self.settings.lighting),
+            (PreviewOption::BlendMode, BlendMode::Mask(_)) => "Blend: Mask".to_string(),
+            (PreviewOption::BlendMode, blend_mode) => format!("Blend: {:?}", blend_mode),
+        }
+    }
+
+    fn cycle(&mut self, option: PreviewOption) {
+        let settings = &mut self.settings;
+
+        match option {
+            PreviewOption::Lighting => {
+                settings.lighting = match settings.lighting {
+                    Lighting::Unlit => Lighting::Pbr,
+                    Lighting::Pbr => Lighting::Unlit,
+                }
+            }
+            PreviewOption::BlendMode => {
+                settings.blend_mode = match settings.blend_mode {
+                    BlendMode::Opaque => BlendMode::Blend,
+                    BlendMode::Blend => BlendMode::Mask(self.cutoff),
+                    BlendMode::Mask(_) => BlendMode::Opaque,
+                }
+            }
+        }
+
+        self.dirty = true;
+    }
+}
+
+impl ComponentsWidget for MaterialPreviewWidget {
+    fn component(&self, _index: usize) -> f32 {
+        self.cutoff
+    }
+
+    fn set_component(&mut self, _index: usize, value: f32) {
+        self.cutoff = value;
+
+        if let BlendMode::Mask(cutoff) = &mut self.settings.blend_mode {
+            *cutoff = value;
+        }
+
+        self.dirty = true;
+    }
+}
+
 impl Widget for MaterialPreviewWidget {
-    type WidgetValue = ();
+    type WidgetValue = OutputSettings;
 
     fn build(
         &mut self,
         entity: Entity,
         commands: &mut Commands,
         area: Vec2,
-        _assets: &Res<DefaultAssets>,
+        assets: &Res<DefaultAssets>,
     ) {
         self.size = area;
+        self.labels.clear();
+
+        let text_style = |font_size| TextStyle {
+            font: assets.font.clone(),
+            font_size,
+            color: Color::WHITE,
+        };
+        let error_text = commands
+            .spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        "",
+                        TextStyle {
+                            color: ERROR_COLOR,
+                            ..text_style(16.0)
+                        },
+                    )
+                    .with_alignment(TextAlignment::Center),
+                    text_anchor: Anchor::TopCenter,
+                    text_2d_bounds: Text2dBounds { size: area },
+                    transform: Transform::from_translation(Vec3::new(0.0, area.y / 2.0, 1.0)),
+                    ..default()
+                },
+                PreviewError,
+            ))
+            .id();
+        let cutoff_label = commands
+            .spawn(Text2dBundle {
+                text: Text::from_section("Mask cutoff", text_style(12.0))
+                    .with_alignment(TextAlignment::Center),
+                transform: Transform::from_xyz(-area.x / 4.0, self.row_center(0), 1.0),
+                ..default()
+            })
+            .id();
+        let mut children = vec![error_text, cutoff_label];
 
-        commands.entity(entity).insert(ReadyForPreview);
+        for option in OPTIONS {
+            let (center, size) = self.option_rect(option);
+            let background = commands
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(0.2, 0.2, 0.2),
+                        custom_size: Some(size - COMPONENT_GAP),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(center.extend(1.0)),
+                    ..default()
+                })
+                .id();
+            let label = commands
+                .spawn(Text2dBundle {
+                    text: Text::from_section(self.option_text(option), text_style(12.0))
+                        .with_alignment(TextAlignment::Center),
+                    transform: Transform::from_translation(center.extend(2.0)),
+                    ..default()
+                })
+                .id();
+
+            children.extend([background, label]);
+            self.labels.push(label);
+        }
+
+        commands
+            .entity(entity)
+            .insert(ReadyForPreview)
+            .push_children(&children);
+        spawn_component_inputs(
+            entity,
+            &[self.cutoff],
+            Vec2::new(area.x / 4.0, self.row_center(0)),
+            area.x / 2.0,
+            commands,
+            assets,
+        );
+    }
+
+    fn can_click(&self) -> bool {
+        true
+    }
+
+    fn clean(&mut self) {
+        self.dirty = false;
+    }
+
+    fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn get_value(&self) -> Self::WidgetValue {
+        self.settings.clone()
     }
 
     fn size(&self) -> Vec2 {
@@ -78,24 +329,200 @@ impl Widget for MaterialPreviewWidget {
 impl SlotWidget<Self, MaterialPreviewWidget> for ShaderNodes {
     fn get_widget(&self) -> Option<MaterialPreviewWidget> {
         match self {
-            ShaderNodes::MaterialPreview => Some(MaterialPreviewWidget::default()),
+            ShaderNodes::MaterialPreview(settings) => Some(MaterialPreviewWidget::new(settings)),
             _ => None,
         }
     }
+
+    fn set_value(&mut self, value: OutputSettings) {
+        if let Self::MaterialPreview(settings) = self {
+            *settings = value;
+        }
+    }
+}
+
+fn click_preview_option(
+    mouse: Res<Input<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut widgets: Query<(&mut MaterialPreviewWidget, &GlobalTransform)>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some(cursor) = world_cursor(&windows, &cameras) else {
+        return;
+    };
+
+    for (mut widget, transform) in widgets.iter_mut() {
+        let local = cursor - transform.translation().truncate();
+        let clicked = OPTIONS.into_iter().find(|option| {
+            let (center, size) = widget.option_rect(*option);
+            let offset = (local - center).abs();
+
+            offset.x <= size.x / 2.0 && offset.y <= size.y / 2.0
+        });
+
+        if let Some(option) = clicked {
+            widget.cycle(option);
+        }
+    }
+}
+
+fn update_preview_options(
+    widgets: Query<&MaterialPreviewWidget, Changed<MaterialPreviewWidget>>,
+    mut texts: Query<&mut Text>,
+) {
+    for widget in widgets.iter() {
+        for (option, entity) in OPTIONS.iter().zip(&widget.labels) {
+            if let Ok(mut text) = texts.get_mut(*entity) {
+                text.sections[0].value = widget.option_text(*option);
+            }
+        }
+    }
 }
 
 #[derive(AsBindGroup, TypeUuid, Debug, Clone, Default)]
 #[uuid = "038b1fc4-f4ff-4735-8442-ff561df3fbf2"]
 pub struct PreviewMaterial {
+    #[uniform(0)]
+    pub parameters: [Vec4; MAX_PARAMETERS],
     #[texture(1)]
     #[sampler(2)]
-    pub texture: Option<Handle<Image>>,
+    pub texture_0: Option<Handle<Image>>,
+    #[texture(3)]
+    #[sampler(4)]
+    pub texture_1: Option<Handle<Image>>,
+    #[texture(5)]
+    #[sampler(6)]
+    pub texture_2: Option<Handle<Image>>,
+    #[texture(7)]
+    #[sampler(8)]
+    pub texture_3: Option<Handle<Image>>,
+    pub alpha_mode: AlphaMode,
+    /// The slot in `parameters` of each parameter of the current shader, by name.
+    pub parameter_slots: HashMap<String, usize>,
+}
+
+impl PreviewMaterial {
+    /// Sets the parameter named `name`, returning `false` if the current shader has none.
+    pub fn set_parameter(&mut self, name: &str, value: Vec4) -> bool {
+        match self.parameter_slots.get(name) {
+            Some(slot) => {
+                self.parameters[*slot] = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The texture bindings in the order the builder allocates them.
+    fn textures_mut(&mut self) -> [&mut Option<Handle<Image>>; MAX_TEXTURES] {
+        [
+            &mut self.texture_0,
+            &mut self.texture_1,
+            &mut self.texture_2,
+            &mut self.texture_3,
+        ]
+    }
+}
+
+/// Images loaded by Texture nodes and the bindings the current shader samples them through.
+#[derive(Resource, Default)]
+pub struct PreviewTextures {
+    /// Loaded images, keyed by path.
+    pub images: HashMap<String, Handle<Image>>,
+    /// The settings of each Texture node in the last resolved graph, one per binding slot.
+    slots: Vec<TextureSettings>,
+    /// Copies of loaded images carrying the sampler of textures that don't use the default one.
+    samplers: HashMap<TextureSettings, Handle<Image>>,
+}
+
+impl PreviewTextures {
+    /// The image to bind for `settings`, copied with its sampler settings if they differ from the
+    /// default, or `None` if it is not loaded yet.
+    fn sampled_image(
+        &mut self,
+        settings: &TextureSettings,
+        images: &mut Assets<Image>,
+    ) -> Option<Handle<Image>> {
+        let source = self.images.get(settings.path.as_ref()?)?;
+
+        if settings.default_sampler() {
+            return Some(source.clone());
+        }
+
+        if let Some(copy) = self.samplers.get(settings) {
+            return Some(copy.clone());
+        }
+
+        let mut image = images.get(source)?.clone();
+
+        image.sampler_descriptor = image_sampler(settings);
+
+        let copy = images.add(image);
+
+        self.samplers.insert(settings.clone(), copy.clone());
+
+        Some(copy)
+    }
 }
 
 impl Material for PreviewMaterial {
+    fn vertex_shader() -> ShaderRef {
+        PREVIEW_VERTEX_SHADER_HANDLE.typed().into()
+    }
+
     fn fragment_shader() -> ShaderRef {
         PREVIEW_SHADER_HANDLE.typed().into()
     }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+}
+
+impl From<AddressMode> for render_resource::AddressMode {
+    fn from(mode: AddressMode) -> Self {
+        match mode {
+            AddressMode::Clamp => Self::ClampToEdge,
+            AddressMode::Repeat => Self::Repeat,
+            AddressMode::Mirror => Self::MirrorRepeat,
+        }
+    }
+}
+
+impl From<FilterMode> for render_resource::FilterMode {
+    fn from(mode: FilterMode) -> Self {
+        match mode {
+            FilterMode::Nearest => Self::Nearest,
+            FilterMode::Linear => Self::Linear,
+        }
+    }
+}
+
+fn image_sampler(settings: &TextureSettings) -> ImageSampler {
+    let address_mode = settings.address_mode.into();
+
+    ImageSampler::Descriptor(SamplerDescriptor {
+        address_mode_u: address_mode,
+        address_mode_v: address_mode,
+        address_mode_w: address_mode,
+        mag_filter: settings.mag_filter.into(),
+        min_filter: settings.min_filter.into(),
+        ..default()
+    })
+}
+
+impl From<BlendMode> for AlphaMode {
+    fn from(mode: BlendMode) -> Self {
+        match mode {
+            BlendMode::Opaque => AlphaMode::Opaque,
+            BlendMode::Blend => AlphaMode::Blend,
+            BlendMode::Mask(cutoff) => AlphaMode::Mask(cutoff),
+        }
+    }
 }
 
 #[derive(Component)]
@@ -115,9 +542,10 @@ fn setup_material_preview(
     query: Query<(Entity, &MaterialPreviewWidget), With<ReadyForPreview>>,
 ) {
     for (entity, widget) in query.iter() {
+        let (center, preview_size) = widget.preview_rect();
         let size = Extent3d {
-            width: widget.size.x as u32,
-            height: widget.size.y as u32,
+            width: preview_size.x as u32,
+            height: preview_size.y as u32,
             ..default()
         };
         let mut image = Image {
@@ -139,10 +567,17 @@ fn setup_material_preview(
         image.resize(size);
 
         let image_handle = images.add(image);
-        let mesh = meshes.add(Mesh::from(shape::UVSphere {
+        let mut mesh = Mesh::from(shape::UVSphere {
             radius: 6.0,
             ..default()
-        }));
+        });
+
+        // Gives the Tangent and Bitangent nodes real tangents to show.
+        if let Err(e) = mesh.generate_tangents() {
+            error!("Failed to generate preview mesh tangents: {}", e);
+        }
+
+        let mesh = meshes.add(mesh);
         let material = materials.add(PreviewMaterial::default());
         let first_pass_layer = RenderLayers::layer(1);
 
@@ -175,6 +610,7 @@ fn setup_material_preview(
         let render_to_entity = commands
             .spawn(SpriteBundle {
                 texture: image_handle.clone(),
+                transform: Transform::from_translation(center.extend(1.0)),
                 ..default()
             })
             .id();
@@ -186,14 +622,176 @@ fn setup_material_preview(
     }
 }
 
+fn bind_preview_textures(
+    mut textures: ResMut<PreviewTextures>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<PreviewMaterial>>,
+    mut ev_image: EventReader<AssetEvent<Image>>,
+) {
+    let loaded = ev_image
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => Some(handle),
+            AssetEvent::Removed { .. } => None,
+        })
+        .filter(|handle| textures.images.values().any(|image| image == *handle))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    if !textures.is_changed() && loaded.is_empty() {
+        return;
+    }
+
+    // Only the materials should see a change, or this would run again next frame.
+    let textures = textures.bypass_change_detection();
+    let PreviewTextures {
+        images: sources,
+        slots,
+        samplers,
+    } = &mut *textures;
+
+    // Copies no Texture node samples anymore are dropped, which frees them, and copies of a
+    // reloaded image still hold its old pixels.
+    samplers.retain(|settings, _| {
+        let reloaded = settings
+            .path
+            .as_ref()
+            .and_then(|path| sources.get(path))
+            .filter(|source| loaded.contains(source))
+            .is_some();
+
+        slots.contains(settings) && !reloaded
+    });
+
+    let bindings = textures
+        .slots
+        .clone()
+        .iter()
+        .map(|settings| textures.sampled_image(settings, &mut images))
+        .collect::<Vec<_>>();
+
+    for (_, material) in materials.iter_mut() {
+        for (index, binding) in material.textures_mut().into_iter().enumerate() {
+            *binding = bindings.get(index).cloned().flatten();
+        }
+    }
+}
+
 fn update_preview_material(
+    mut commands: Commands,
+    assets: Res<DefaultAssets>,
+    mut materials: ResMut<Assets<PreviewMaterial>>,
+    mut textures: ResMut<PreviewTextures>,
     mut shaders: ResMut<Assets<Shader>>,
-    mut ev_node: EventReader<FlowNodeEvent<ShaderNodes>>,
+    mut ev_resolved: EventReader<ShaderResolved>,
+    mut error_text: Query<&mut Text, With<PreviewError>>,
+    node_errors: Query<Entity, With<NodeError>>,
+    sprites: Query<&Sprite>,
 ) {
-    if let Some(FlowNodeEvent::Resolved((_, value))) = ev_node.iter().next() {
-        let shader_str = value.build().unwrap();
-        let shader_handle = shaders.get_mut(&PREVIEW_SHADER_HANDLE.typed()).unwrap();
+    if let Some(ShaderResolved { builder: value, .. }) =
+        ev_resolved.iter().filter(|event| event.output).last()
+    {
+        let result = value
+            .build()
+            .and_then(|shader_str| value.validate(&shader_str).map(|_| shader_str));
+        let mut errors = value.errors.clone();
+
+        if let Err(e) = &result {
+            if !errors.contains(e) {
+                errors.push(e.clone());
+            }
+        }
+
+        for entity in node_errors.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+
+        for error in &errors {
+            let Some(node) = error.node() else {
+                continue;
+            };
+            // Nodes are drawn as sprites centered on their position, so the label goes above.
+            let top = sprites
+                .get(node.entity)
+                .ok()
+                .and_then(|sprite| sprite.custom_size)
+                .map_or(0.0, |size| size.y / 2.0);
+            // The node may have been removed or rebuilt since it was resolved.
+            let Some(mut parent) = commands.get_entity(node.entity) else {
+                continue;
+            };
+
+            parent.with_children(|parent| {
+                parent.spawn((
+                    Text2dBundle {
+                        text: Text::from_section(
+                            error.to_string(),
+                            TextStyle {
+                                font: assets.font.clone(),
+                                font_size: 14.0,
+                                color: ERROR_COLOR,
+                            },
+                        ),
+                        text_anchor: Anchor::BottomCenter,
+                        transform: Transform::from_xyz(0.0, top + 4.0, 1.0),
+                        ..default()
+                    },
+                    NodeError,
+                ));
+            });
+        }
+
+        // An invalid shader leaves the last good one on the preview, along with the parameters,
+        // textures and blend mode it was built for.
+        let message = match result {
+            Ok(shader_str) => {
+                for (_, material) in materials.iter_mut() {
+                    material.parameter_slots = value
+                        .parameters
+                        .iter()
+                        .enumerate()
+                        .map(|(slot, (_, parameter))| (parameter.name.clone(), slot))
+                        .collect();
 
-        *shader_handle = Shader::from_wgsl(shader_str);
+                    for (_, parameter) in &value.parameters {
+                        material.set_parameter(&parameter.name, Vec4::from_array(parameter.value));
+                    }
+
+                    material.alpha_mode = value.blend_mode.into();
+                }
+
+                let slots = value
+                    .textures
+                    .iter()
+                    .map(|(_, settings)| settings.clone())
+                    .collect::<Vec<_>>();
+
+                if textures.slots != slots {
+                    textures.slots = slots;
+                }
+
+                // A generated vertex stage lives in the same module as the fragment stage.
+                let vertex_str = match value.vertex {
+                    Some(_) => shader_str.clone(),
+                    None => PREVIEW_VERTEX_SHADER.to_string(),
+                };
+
+                if let Some(shader_handle) = shaders.get_mut(&PREVIEW_VERTEX_SHADER_HANDLE.typed())
+                {
+                    *shader_handle = Shader::from_wgsl(vertex_str);
+                }
+
+                if let Some(shader_handle) = shaders.get_mut(&PREVIEW_SHADER_HANDLE.typed()) {
+                    *shader_handle = Shader::from_wgsl(shader_str);
+                }
+
+                String::new()
+            }
+            Err(e) => e.to_string(),
+        };
+
+        for mut text in error_text.iter_mut() {
+            text.sections[0].value = message.clone();
+        }
     }
 }
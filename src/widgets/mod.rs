@@ -1,4 +1,8 @@
-use bevy::app::{PluginGroup, PluginGroupBuilder};
+use bevy::{
+    app::{PluginGroup, PluginGroupBuilder},
+    prelude::*,
+    window::PrimaryWindow,
+};
 use bevy_flow_node::{
     widget::SlotWidget,
     widgets::{InputWidget, InputWidgetPlugin, NumberInput},
@@ -6,11 +10,18 @@ use bevy_flow_node::{
 
 use crate::shader::ShaderNodes;
 
+mod color_widget;
+mod component_inputs;
 mod material_preview_widget;
+mod parameter_widget;
 mod texture_widget;
+mod vector_widget;
 
+use color_widget::ColorWidgetPlugin;
 pub use material_preview_widget::MaterialPreviewWidgetPlugin;
+use parameter_widget::ParameterWidgetPlugin;
 use texture_widget::TextureWidgetPlugin;
+use vector_widget::VectorWidgetPlugin;
 
 #[derive(Default)]
 pub struct WidgetPlugins;
@@ -18,8 +29,11 @@ pub struct WidgetPlugins;
 impl PluginGroup for WidgetPlugins {
     fn build(self) -> PluginGroupBuilder {
         PluginGroupBuilder::start::<Self>()
+            .add(ColorWidgetPlugin)
             .add(MaterialPreviewWidgetPlugin)
+            .add(ParameterWidgetPlugin)
             .add(TextureWidgetPlugin)
+            .add(VectorWidgetPlugin)
             .add(InputWidgetPlugin::<ShaderNodes, NumberInput>::default())
     }
 }
@@ -27,14 +41,31 @@ impl PluginGroup for WidgetPlugins {
 impl SlotWidget<Self, InputWidget<NumberInput>> for ShaderNodes {
     fn get_widget(&self) -> Option<InputWidget<NumberInput>> {
         match self {
-            ShaderNodes::Extend(_) => Some(InputWidget::default()),
+            ShaderNodes::Extend(_) | ShaderNodes::Float(_) | ShaderNodes::Fresnel(_) => {
+                Some(InputWidget::default())
+            }
             _ => None,
         }
     }
 
     fn set_value(&mut self, value: NumberInput) {
-        if let Self::Extend(v) = self {
-            *v = value;
+        match self {
+            Self::Extend(v) | Self::Float(v) | Self::Fresnel(v) => *v = value,
+            _ => {}
         }
     }
 }
+
+/// The cursor position in the editor's world space, for widgets handling clicks themselves.
+fn world_cursor(
+    windows: &Query<&Window, With<PrimaryWindow>>,
+    cameras: &Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+) -> Option<Vec2> {
+    let cursor = windows.get_single().ok()?.cursor_position()?;
+
+    cameras.iter().find_map(|(camera, transform)| {
+        camera
+            .viewport_to_world(transform, cursor)
+            .map(|ray| ray.origin.truncate())
+    })
+}
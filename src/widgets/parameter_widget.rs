@@ -0,0 +1,252 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_flow_node::{
+    assets::DefaultAssets,
+    widget::{Widget, WidgetPlugin},
+    SlotWidget,
+};
+
+use crate::shader::{ShaderNodes, ShaderParameter};
+
+use super::{
+    component_inputs::{
+        read_component_inputs,
+        spawn_component_inputs,
+        ComponentsWidget,
+        COMPONENT_GAP,
+        COMPONENT_HEIGHT,
+    },
+    world_cursor,
+};
+
+const NAME_HEIGHT: f32 = 20.0;
+
+#[derive(Default)]
+pub struct ParameterWidgetPlugin;
+
+impl Plugin for ParameterWidgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(WidgetPlugin::<ShaderNodes, ParameterWidget>::default())
+            .add_system(select_parameter_name)
+            .add_system(edit_parameter_name)
+            .add_system(read_component_inputs::<ParameterWidget>)
+            .add_system(update_parameter_widget);
+    }
+}
+
+/// The name of a Parameter node, edited by clicking it and typing, above an input per component
+/// of its value.
+#[derive(Component, Clone, Default)]
+pub struct ParameterWidget {
+    pub size: Vec2,
+    pub parameter: ShaderParameter,
+    /// The name typed so far while editing it.
+    editing: Option<String>,
+    name_text: Option<Entity>,
+    dirty: bool,
+}
+
+impl ParameterWidget {
+    /// Center of the name field, relative to the widget.
+    fn name_center(&self) -> f32 {
+        self.size.y / 2.0 - NAME_HEIGHT / 2.0
+    }
+
+    fn name_display(&self) -> String {
+        match &self.editing {
+            Some(text) => format!("{}|", text),
+            None => self.parameter.name.clone(),
+        }
+    }
+
+    /// Renames the parameter to the text being edited, unless it is empty, and stops editing.
+    fn commit(&mut self) {
+        if let Some(name) = self.editing.take() {
+            if !name.is_empty() && name != self.parameter.name {
+                self.parameter.name = name;
+                self.dirty = true;
+            }
+        }
+    }
+}
+
+impl ComponentsWidget for ParameterWidget {
+    fn component(&self, index: usize) -> f32 {
+        self.parameter.value[index]
+    }
+
+    fn set_component(&mut self, index: usize, value: f32) {
+        self.parameter.value[index] = value;
+        self.dirty = true;
+    }
+}
+
+impl Widget for ParameterWidget {
+    type WidgetValue = ShaderParameter;
+
+    fn build(
+        &mut self,
+        entity: Entity,
+        commands: &mut Commands,
+        area: Vec2,
+        assets: &Res<DefaultAssets>,
+    ) {
+        self.size = area;
+
+        let y = self.name_center();
+        let background = commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    color: Color::rgb(0.2, 0.2, 0.2),
+                    custom_size: Some(Vec2::new(area.x - 2.0 * COMPONENT_GAP, NAME_HEIGHT)),
+                    ..default()
+                },
+                transform: Transform::from_xyz(0.0, y, 1.0),
+                ..default()
+            })
+            .id();
+        let text = commands
+            .spawn(Text2dBundle {
+                text: Text::from_section(
+                    self.name_display(),
+                    TextStyle {
+                        font: assets.font.clone(),
+                        font_size: 14.0,
+                        color: Color::WHITE,
+                    },
+                )
+                .with_alignment(TextAlignment::Center),
+                transform: Transform::from_xyz(0.0, y, 2.0),
+                ..default()
+            })
+            .id();
+
+        self.name_text = Some(text);
+        commands.entity(entity).push_children(&[background, text]);
+
+        let components = self.parameter.io.components();
+
+        spawn_component_inputs(
+            entity,
+            &self.parameter.value[..components],
+            Vec2::new(0.0, -area.y / 2.0 + COMPONENT_HEIGHT / 2.0),
+            area.x,
+            commands,
+            assets,
+        );
+    }
+
+    fn can_click(&self) -> bool {
+        true
+    }
+
+    fn clean(&mut self) {
+        self.dirty = false;
+    }
+
+    fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn get_value(&self) -> Self::WidgetValue {
+        self.parameter.clone()
+    }
+
+    fn size(&self) -> Vec2 {
+        self.size
+    }
+}
+
+impl SlotWidget<Self, ParameterWidget> for ShaderNodes {
+    fn get_widget(&self) -> Option<ParameterWidget> {
+        match self {
+            ShaderNodes::Parameter(parameter) => Some(ParameterWidget {
+                parameter: parameter.clone(),
+                ..default()
+            }),
+            _ => None,
+        }
+    }
+
+    fn set_value(&mut self, value: ShaderParameter) {
+        if let Self::Parameter(parameter) = self {
+            parameter.name = value.name;
+            parameter.value = value.value;
+        }
+    }
+}
+
+fn select_parameter_name(
+    mouse: Res<Input<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut widgets: Query<(&mut ParameterWidget, &GlobalTransform)>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some(cursor) = world_cursor(&windows, &cameras) else {
+        return;
+    };
+
+    for (mut widget, transform) in widgets.iter_mut() {
+        let local = cursor - transform.translation().truncate();
+        let hit = local.x.abs() <= widget.size.x / 2.0
+            && (local.y - widget.name_center()).abs() <= NAME_HEIGHT / 2.0;
+
+        if widget.editing.is_some() {
+            widget.commit();
+        }
+
+        if hit {
+            widget.editing = Some(widget.parameter.name.clone());
+        }
+    }
+}
+
+fn edit_parameter_name(
+    keys: Res<Input<KeyCode>>,
+    mut ev_char: EventReader<ReceivedCharacter>,
+    mut widgets: Query<&mut ParameterWidget>,
+) {
+    // Names become WGSL field names, so only keep characters valid in identifiers.
+    let typed = ev_char
+        .iter()
+        .map(|event| event.char)
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_')
+        .collect::<String>();
+
+    for mut widget in widgets.iter_mut() {
+        if widget.editing.is_none() {
+            continue;
+        }
+
+        if keys.just_pressed(KeyCode::Return) {
+            widget.commit();
+        } else if keys.just_pressed(KeyCode::Escape) {
+            widget.editing = None;
+        } else if !typed.is_empty() || keys.just_pressed(KeyCode::Back) {
+            if let Some(text) = &mut widget.editing {
+                if keys.just_pressed(KeyCode::Back) {
+                    text.pop();
+                }
+
+                text.push_str(&typed);
+            }
+        }
+    }
+}
+
+fn update_parameter_widget(
+    widgets: Query<&ParameterWidget, Changed<ParameterWidget>>,
+    mut texts: Query<&mut Text>,
+) {
+    for widget in widgets.iter() {
+        if let Some(mut text) = widget
+            .name_text
+            .and_then(|entity| texts.get_mut(entity).ok())
+        {
+            text.sections[0].value = widget.name_display();
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use bevy::{prelude::*, render::texture::DEFAULT_IMAGE_HANDLE, sprite::Anchor};
+use bevy::{prelude::*, render::texture::DEFAULT_IMAGE_HANDLE, window::PrimaryWindow};
 use bevy_flow_node::{
     assets::DefaultAssets,
     widget::{Widget, WidgetPlugin},
@@ -6,9 +6,12 @@ use bevy_flow_node::{
 };
 use nfd::Response;
 
-use crate::shader::ShaderNodes;
+use crate::shader::{ShaderNodes, TextureSettings};
 
-use super::material_preview_widget::PreviewMaterial;
+use super::{material_preview_widget::PreviewTextures, world_cursor};
+
+const OPTION_HEIGHT: f32 = 18.0;
+const OPTION_GAP: f32 = 2.0;
 
 #[derive(Default)]
 pub struct TextureWidgetPlugin;
@@ -16,57 +19,200 @@ pub struct TextureWidgetPlugin;
 impl Plugin for TextureWidgetPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugin(WidgetPlugin::<ShaderNodes, TextureWidget>::default())
-            .add_system(load_texture);
+            .add_system(click_texture_widget)
+            .add_system(load_texture)
+            .add_system(update_texture_widget);
     }
 }
 
+/// A sampler setting shown as a button under the image, changed to the next mode when clicked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TextureOption {
+    Wrap,
+    MagFilter,
+    MinFilter,
+}
+
+const OPTIONS: [TextureOption; 3] = [
+    TextureOption::Wrap,
+    TextureOption::MagFilter,
+    TextureOption::MinFilter,
+];
+
+/// The image of a Texture node, which opens a file dialog when clicked, above a button per
+/// sampler setting.
 #[derive(Component, Clone, Default)]
 pub struct TextureWidget {
     pub size: Vec2,
-    pub texture: Option<Handle<Image>>,
+    pub settings: TextureSettings,
     pub to_load: Option<String>,
+    image: Option<Entity>,
+    labels: Vec<Entity>,
+    dirty: bool,
+}
+
+impl TextureWidget {
+    /// Center and size of the image, relative to the widget.
+    fn image_rect(&self) -> (Vec2, Vec2) {
+        let options_height = 2.0 * OPTION_HEIGHT;
+
+        (
+            Vec2::new(0.0, options_height / 2.0),
+            Vec2::new(self.size.x, self.size.y - options_height),
+        )
+    }
+
+    /// Center and size of the button for `option`, relative to the widget.
+    ///
+    /// The wrap mode takes the first row under the image, the filters share the second.
+    fn option_rect(&self, option: TextureOption) -> (Vec2, Vec2) {
+        let bottom = -self.size.y / 2.0;
+        let half = self.size.x / 2.0;
+
+        match option {
+            TextureOption::Wrap => (
+                Vec2::new(0.0, bottom + 1.5 * OPTION_HEIGHT),
+                Vec2::new(self.size.x, OPTION_HEIGHT),
+            ),
+            TextureOption::MagFilter => (
+                Vec2::new(-half / 2.0, bottom + 0.5 * OPTION_HEIGHT),
+                Vec2::new(half, OPTION_HEIGHT),
+            ),
+            TextureOption::MinFilter => (
+                Vec2::new(half / 2.0, bottom + 0.5 * OPTION_HEIGHT),
+                Vec2::new(half, OPTION_HEIGHT),
+            ),
+        }
+    }
+
+    fn option_text(&self, option: TextureOption) -> String {
+        match option {
+            TextureOption::Wrap => format!("Wrap: {:?}", self.settings.address_mode),
+            TextureOption::MagFilter => format!("Mag: {:?}", self.settings.mag_filter),
+            TextureOption::MinFilter => format!("Min: {:?}", self.settings.min_filter),
+        }
+    }
+
+    fn cycle(&mut self, option: TextureOption) {
+        let settings = &mut self.settings;
+
+        match option {
+            TextureOption::Wrap => settings.address_mode = settings.address_mode.next(),
+            TextureOption::MagFilter => settings.mag_filter = settings.mag_filter.next(),
+            TextureOption::MinFilter => settings.min_filter = settings.min_filter.next(),
+        }
+
+        self.dirty = true;
+    }
+
+    fn pick_image(&mut self) {
+        let result = match nfd::open_file_dialog(Some("png"), None) {
+            Ok(result) => result,
+            Err(e) => {
+                error!("Failed to open file dialog: {}", e);
+                return;
+            }
+        };
+
+        match result {
+            Response::Okay(file_path) => {
+                self.settings.path = Some(file_path.clone());
+                self.to_load = Some(file_path);
+                self.dirty = true;
+            }
+            Response::OkayMultiple(_) => {}
+            Response::Cancel => {}
+        }
+    }
+}
+
+fn contains((center, size): (Vec2, Vec2), point: Vec2) -> bool {
+    let offset = (point - center).abs();
+
+    offset.x <= size.x / 2.0 && offset.y <= size.y / 2.0
 }
 
 impl Widget for TextureWidget {
-    type WidgetValue = ();
+    type WidgetValue = TextureSettings;
 
     fn build(
         &mut self,
         entity: Entity,
         commands: &mut Commands,
         area: Vec2,
-        _assets: &Res<DefaultAssets>,
+        assets: &Res<DefaultAssets>,
     ) {
         self.size = area;
+        self.labels.clear();
 
-        commands.entity(entity).insert((
-            Sprite {
-                custom_size: Some(self.size),
-                anchor: Anchor::Center,
+        let (image_center, image_size) = self.image_rect();
+        let image = commands
+            .spawn(SpriteBundle {
+                sprite: Sprite {
+                    custom_size: Some(image_size),
+                    ..default()
+                },
+                texture: DEFAULT_IMAGE_HANDLE.typed(),
+                transform: Transform::from_translation(image_center.extend(1.0)),
                 ..default()
-            },
-            Visibility::Inherited,
-            ComputedVisibility::default(),
-            DEFAULT_IMAGE_HANDLE.typed::<Image>(),
-        ));
+            })
+            .id();
+        let mut children = vec![image];
+
+        for option in OPTIONS {
+            let (center, size) = self.option_rect(option);
+            let background = commands
+                .spawn(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::rgb(0.2, 0.2, 0.2),
+                        custom_size: Some(size - OPTION_GAP),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(center.extend(1.0)),
+                    ..default()
+                })
+                .id();
+            let label = commands
+                .spawn(Text2dBundle {
+                    text: Text::from_section(
+                        self.option_text(option),
+                        TextStyle {
+                            font: assets.font.clone(),
+                            font_size: 12.0,
+                            color: Color::WHITE,
+                        },
+                    )
+                    .with_alignment(TextAlignment::Center),
+                    transform: Transform::from_translation(center.extend(2.0)),
+                    ..default()
+                })
+                .id();
+
+            children.extend([background, label]);
+            self.labels.push(label);
+        }
+
+        self.image = Some(image);
+        commands
+            .entity(entity)
+            .insert((Visibility::Inherited, ComputedVisibility::default()))
+            .push_children(&children);
     }
 
     fn can_click(&self) -> bool {
         true
     }
 
-    fn focus(&mut self) {
-        let result = nfd::open_file_dialog(Some("png"), None).unwrap_or_else(|e| {
-            panic!("{}", e);
-        });
+    fn clean(&mut self) {
+        self.dirty = false;
+    }
 
-        match result {
-            Response::Okay(file_path) => {
-                self.to_load = Some(file_path);
-            }
-            Response::OkayMultiple(_) => {}
-            Response::Cancel => {}
-        }
+    fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn get_value(&self) -> Self::WidgetValue {
+        self.settings.clone()
     }
 
     fn size(&self) -> Vec2 {
@@ -77,29 +223,79 @@ impl Widget for TextureWidget {
 impl SlotWidget<Self, TextureWidget> for ShaderNodes {
     fn get_widget(&self) -> Option<TextureWidget> {
         match self {
-            ShaderNodes::Texture => Some(TextureWidget::default()),
+            ShaderNodes::Texture(settings) => Some(TextureWidget {
+                settings: settings.clone(),
+                to_load: settings.path.clone(),
+                ..default()
+            }),
             _ => None,
         }
     }
+
+    fn set_value(&mut self, value: TextureSettings) {
+        if let Self::Texture(settings) = self {
+            *settings = value;
+        }
+    }
+}
+
+fn click_texture_widget(
+    mouse: Res<Input<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut widgets: Query<(&mut TextureWidget, &GlobalTransform)>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some(cursor) = world_cursor(&windows, &cameras) else {
+        return;
+    };
+
+    for (mut widget, transform) in widgets.iter_mut() {
+        let local = cursor - transform.translation().truncate();
+
+        if contains(widget.image_rect(), local) {
+            widget.pick_image();
+        } else if let Some(option) = OPTIONS
+            .into_iter()
+            .find(|option| contains(widget.option_rect(*option), local))
+        {
+            widget.cycle(option);
+        }
+    }
 }
 
 fn load_texture(
     mut commands: Commands,
     server: Res<AssetServer>,
-    mut materials: ResMut<Assets<PreviewMaterial>>,
-    mut texture_widgets: Query<(Entity, &mut TextureWidget)>,
+    mut textures: ResMut<PreviewTextures>,
+    mut texture_widgets: Query<&mut TextureWidget>,
 ) {
-    let mut materials = materials.iter_mut().collect::<Vec<_>>();
+    for mut widget in texture_widgets.iter_mut() {
+        let Some(image) = widget.image else {
+            continue;
+        };
 
-    for (entity, mut widget) in texture_widgets.iter_mut() {
         if let Some(path) = widget.to_load.take() {
-            let handle: Handle<Image> = server.load(path);
+            let handle: Handle<Image> = server.load(path.as_str());
 
-            for mut material in &mut materials {
-                material.1.texture = Some(handle.clone());
-            }
+            textures.images.insert(path, handle.clone());
+            commands.entity(image).insert(handle);
+        }
+    }
+}
 
-            commands.entity(entity).insert(handle);
+fn update_texture_widget(
+    widgets: Query<&TextureWidget, Changed<TextureWidget>>,
+    mut texts: Query<&mut Text>,
+) {
+    for widget in widgets.iter() {
+        for (option, entity) in OPTIONS.iter().zip(&widget.labels) {
+            if let Ok(mut text) = texts.get_mut(*entity) {
+                text.sections[0].value = widget.option_text(*option);
+            }
         }
     }
 }
@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use bevy_flow_node::{
+    assets::DefaultAssets,
+    widget::{Widget, WidgetPlugin},
+    SlotWidget,
+};
+
+use crate::shader::ShaderNodes;
+
+use super::component_inputs::{read_component_inputs, spawn_component_inputs, ComponentsWidget};
+
+#[derive(Default)]
+pub struct VectorWidgetPlugin;
+
+impl Plugin for VectorWidgetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(WidgetPlugin::<ShaderNodes, VectorWidget>::default())
+            .add_system(read_component_inputs::<VectorWidget>);
+    }
+}
+
+/// A row of number inputs, one per vector component or unconnected input default.
+#[derive(Component, Clone, Default)]
+pub struct VectorWidget {
+    pub size: Vec2,
+    pub values: [f32; 4],
+    count: usize,
+    dirty: bool,
+}
+
+impl VectorWidget {
+    fn new(values: &[f32]) -> Self {
+        let mut widget = Self {
+            count: values.len(),
+            ..default()
+        };
+
+        widget.values[..values.len()].copy_from_slice(values);
+        widget
+    }
+}
+
+impl ComponentsWidget for VectorWidget {
+    fn component(&self, index: usize) -> f32 {
+        self.values[index]
+    }
+
+    fn set_component(&mut self, index: usize, value: f32) {
+        self.values[index] = value;
+        self.dirty = true;
+    }
+}
+
+impl Widget for VectorWidget {
+    type WidgetValue = [f32; 4];
+
+    fn build(
+        &mut self,
+        entity: Entity,
+        commands: &mut Commands,
+        area: Vec2,
+        assets: &Res<DefaultAssets>,
+    ) {
+        self.size = area;
+
+        spawn_component_inputs(
+            entity,
+            &self.values[..self.count],
+            Vec2::ZERO,
+            area.x,
+            commands,
+            assets,
+        );
+    }
+
+    fn can_click(&self) -> bool {
+        true
+    }
+
+    fn clean(&mut self) {
+        self.dirty = false;
+    }
+
+    fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn get_value(&self) -> Self::WidgetValue {
+        self.values
+    }
+
+    fn size(&self) -> Vec2 {
+        self.size
+    }
+}
+
+impl SlotWidget<Self, VectorWidget> for ShaderNodes {
+    fn get_widget(&self) -> Option<VectorWidget> {
+        match self {
+            ShaderNodes::Add(values)
+            | ShaderNodes::Divide(values)
+            | ShaderNodes::Modulo(values)
+            | ShaderNodes::Multiply(values)
+            | ShaderNodes::PerlinNoise(values)
+            | ShaderNodes::Power(values)
+            | ShaderNodes::SimplexNoise(values)
+            | ShaderNodes::Subtract(values)
+            | ShaderNodes::ValueNoise(values)
+            | ShaderNodes::Vec2(values)
+            | ShaderNodes::Voronoi(values) => Some(VectorWidget::new(values)),
+            ShaderNodes::Vec3(values) => Some(VectorWidget::new(values)),
+            ShaderNodes::Vec4(values) => Some(VectorWidget::new(values)),
+            _ => None,
+        }
+    }
+
+    fn set_value(&mut self, value: [f32; 4]) {
+        match self {
+            Self::Add(values)
+            | Self::Divide(values)
+            | Self::Modulo(values)
+            | Self::Multiply(values)
+            | Self::PerlinNoise(values)
+            | Self::Power(values)
+            | Self::SimplexNoise(values)
+            | Self::Subtract(values)
+            | Self::ValueNoise(values)
+            | Self::Vec2(values)
+            | Self::Voronoi(values) => values.copy_from_slice(&value[..2]),
+            Self::Vec3(values) => values.copy_from_slice(&value[..3]),
+            Self::Vec4(values) => *values = value,
+            _ => {}
+        }
+    }
+}